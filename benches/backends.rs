@@ -0,0 +1,109 @@
+//! Times the programs below through every backend, run with `cargo bench`.
+//!
+//! Each program is parsed once and then run a fixed amount of times per backend, the x86 timings
+//! include assembling and linking since that is part of running a program with that backend.
+//! Parsing is timed separately on long expressions, doubling the amount of operators should about
+//! double the time.
+
+use dyno::backend::{interp, x86_backend};
+use dyno::error::DynoResult;
+use dyno::lexer::lex;
+use dyno::parser::parse;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 10;
+
+const EXPRESSION_LENGTHS: [usize; 3] = [1000, 2000, 4000];
+
+const PROGRAMS: [(&str, &str); 2] = [
+    (
+        "arithmetic",
+        r"
+        let a: u64;
+        let b: u64;
+        a = 0;
+        b = 7;
+        while a < 20000 {
+            b = (b * 31 + a) / 3 + b * 7 - a;
+            a = a + 1;
+        }
+        return b;",
+    ),
+    (
+        "loops",
+        r"
+        let i: u32;
+        let j: u32;
+        let count: u32;
+        i = 0;
+        count = 0;
+        while i < 200 {
+            j = 0;
+            while j < 200 {
+                count = count + 1;
+                j = j + 1;
+            }
+            i = i + 1;
+        }
+        return count;",
+    ),
+];
+
+fn time<F: FnMut() -> DynoResult<u64>>(mut run: F) -> DynoResult<(u64, Duration)> {
+    let mut result = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        result = run()?;
+    }
+
+    Ok((result, start.elapsed() / ITERATIONS))
+}
+
+/// Times parsing an expression with the given amount of additions, lexing is left out.
+fn time_parse(length: usize) -> DynoResult<Duration> {
+    let terms: Vec<&str> = (0..=length)
+        .map(|i| if i % 2 == 0 { "a" } else { "b" })
+        .collect();
+    let source = format!("let a: u8; let b: u32; return {};", terms.join(" + "));
+
+    let mut total = Duration::default();
+    for _ in 0..ITERATIONS {
+        let tokens = lex(&source)?;
+        let start = Instant::now();
+        parse(tokens)?;
+        total += start.elapsed();
+    }
+
+    Ok(total / ITERATIONS)
+}
+
+fn main() -> DynoResult<()> {
+    println!(
+        "{:<12} {:<8} {:>14} {:>12}",
+        "program", "backend", "time", "result"
+    );
+
+    for (name, source) in &PROGRAMS {
+        let ast = parse(lex(source)?)?;
+
+        let backends: [(&str, &dyn Fn() -> DynoResult<u64>); 2] = [
+            ("interp", &|| interp::run(&ast)),
+            ("x86", &|| x86_backend::compile_and_run(&ast)),
+        ];
+
+        for (backend, run) in &backends {
+            let (result, duration) = time(run)?;
+            println!(
+                "{:<12} {:<8} {:>14?} {:>12}",
+                name, backend, duration, result
+            );
+        }
+    }
+
+    println!("\n{:<12} {:>14}", "operators", "parse time");
+    for length in &EXPRESSION_LENGTHS {
+        println!("{:<12} {:>14?}", length, time_parse(*length)?);
+    }
+
+    Ok(())
+}
@@ -2,7 +2,7 @@ use crate::error::*;
 use crate::scope::Scope;
 use crate::token::TokenType;
 use crate::types::{DynoType, DynoValue};
-use std::cmp::Ordering;
+use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BinaryOperationType {
@@ -18,22 +18,147 @@ pub enum BinaryOperationType {
     GreaterThanEqual,
 }
 
+/// How a cast to a narrower type handles values which don't fit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CastType {
+    /// Only keeps the lower bits, written as `as`.
+    Truncate,
+    /// Clamps the value to the maximum of the type, written as `as!`.
+    Saturate,
+}
+
+/// A function provided by the language, called like `read_int()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    /// Reads an unsigned integer from stdin, this results in 0 when no integer can be read.
+    ReadInt,
+    /// The amount of set bits in an integer.
+    CountOnes,
+    /// The amount of unset bits above the highest set bit, within the width of the integer.
+    LeadingZeros,
+    /// The amount of unset bits below the lowest set bit, this is the width of the integer for 0.
+    TrailingZeros,
+    /// Methods called like `a.wrapping_add(b)`, the receiver is the first argument and the result
+    /// has its type.
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    SaturatingAdd,
+    SaturatingSub,
+    SaturatingMul,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Expression {
     BinaryOperation(BinaryOperationType, Box<Expression>, Box<Expression>),
     Literal(DynoType, DynoValue),
     Widen(Box<Expression>, DynoType),
     Identifier(String),
+    Block(Vec<Statement>, Box<Expression>),
+    Assign(String, Box<Expression>),
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    Cast(Box<Expression>, DynoType, CastType),
+    /// A builtin call, every argument is stored together with its type.
+    Call(Builtin, Vec<(Expression, DynoType)>),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum Statement {
     Declaration(String, DynoType),
+    /// A named constant, references to it are replaced by its value while parsing.
+    Constant(String, DynoType, DynoValue),
     Assignment(String, Expression),
-    If(Expression, Box<Statement>),
+    If(Expression, Box<Statement>, Option<Box<Statement>>),
     While(Expression, Box<Statement>),
+    /// A loop which checks its condition after running the body, so the body runs at least once.
+    DoWhile(Box<Statement>, Expression),
     Return(Expression),
     Block(Vec<Statement>),
+    Assert(Expression),
+    /// Exits the innermost loop, or the loop with the given label.
+    Break(Option<String>),
+    /// Jumps to the condition of the innermost loop, or of the loop with the given label.
+    Continue(Option<String>),
+    /// A loop with a label which `break` and `continue` can refer to.
+    Labeled(String, Box<Statement>),
+    /// A statement with the comments written around it, only produced when comments are kept.
+    Commented(Comments, Box<Statement>),
+}
+
+/// The comments attached to a statement.
+#[derive(Debug, Default, PartialEq)]
+pub struct Comments {
+    /// The comments on the lines above the statement.
+    pub leading: Vec<String>,
+    /// The comment at the end of the last line of the statement.
+    pub trailing: Option<String>,
+    /// The comments below the last statement of a block or of the program.
+    pub after: Vec<String>,
+}
+
+impl Comments {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_none() && self.after.is_empty()
+    }
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "read_int" => Some(Self::ReadInt),
+            "count_ones" => Some(Self::CountOnes),
+            "leading_zeros" => Some(Self::LeadingZeros),
+            "trailing_zeros" => Some(Self::TrailingZeros),
+            _ => None,
+        }
+    }
+
+    pub fn from_method(name: &str) -> Option<Self> {
+        match name {
+            "wrapping_add" => Some(Self::WrappingAdd),
+            "wrapping_sub" => Some(Self::WrappingSub),
+            "wrapping_mul" => Some(Self::WrappingMul),
+            "saturating_add" => Some(Self::SaturatingAdd),
+            "saturating_sub" => Some(Self::SaturatingSub),
+            "saturating_mul" => Some(Self::SaturatingMul),
+            _ => None,
+        }
+    }
+
+    pub fn is_method(&self) -> bool {
+        self.parameter_count() == 2
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ReadInt => "read_int",
+            Self::CountOnes => "count_ones",
+            Self::LeadingZeros => "leading_zeros",
+            Self::TrailingZeros => "trailing_zeros",
+            Self::WrappingAdd => "wrapping_add",
+            Self::WrappingSub => "wrapping_sub",
+            Self::WrappingMul => "wrapping_mul",
+            Self::SaturatingAdd => "saturating_add",
+            Self::SaturatingSub => "saturating_sub",
+            Self::SaturatingMul => "saturating_mul",
+        }
+    }
+
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            Self::ReadInt => 0,
+            Self::CountOnes | Self::LeadingZeros | Self::TrailingZeros => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn return_type(&self, arguments: &[(Expression, DynoType)]) -> DynoType {
+        match self {
+            Self::ReadInt => DynoType::UInt64(),
+            Self::CountOnes | Self::LeadingZeros | Self::TrailingZeros => DynoType::UInt32(),
+            _ => arguments[0].1,
+        }
+    }
 }
 
 impl BinaryOperationType {
@@ -85,115 +210,309 @@ impl BinaryOperationType {
             Self::GreaterThanEqual => 3,
         }
     }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Self::Equal
+                | Self::NotEqual
+                | Self::LessThan
+                | Self::LessThanEqual
+                | Self::GreaterThan
+                | Self::GreaterThanEqual
+        )
+    }
+
+    pub fn is_arithmetic(&self) -> bool {
+        !self.is_comparison()
+    }
+
+    /// Returns the type of the result when the operation is applied to operands of the given type.
+    pub fn result_type(&self, operand: DynoType) -> DynoType {
+        if self.is_comparison() {
+            DynoType::Bool()
+        } else {
+            operand
+        }
+    }
+
+    /// Checks that the operation can be applied to operands of the given types and returns the
+    /// type of the result.
+    pub fn check_operands(&self, left: DynoType, right: DynoType) -> DynoResult<DynoType> {
+        let compatible = if self.is_comparison() {
+            left == right
+        } else {
+            left.is_int() && right.is_int() && (left.get_bits() == right.get_bits())
+        };
+
+        if compatible {
+            Ok(self.result_type(left))
+        } else {
+            Err(DynoError::IncompatibleTypeError(left, right, None))
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BinaryOperationType::*;
+
+        let symbol = match self {
+            Add => "+",
+            Subtract => "-",
+            Multiply => "*",
+            Divide => "/",
+            Equal => "==",
+            NotEqual => "!=",
+            LessThan => "<",
+            LessThanEqual => "<=",
+            GreaterThan => ">",
+            GreaterThanEqual => ">=",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Statement {
+    /// Returns the statement without the comments attached to it.
+    pub fn without_comments(&self) -> &Statement {
+        match self {
+            Statement::Commented(_, statement) => statement.without_comments(),
+            _ => self,
+        }
+    }
 }
 
 impl Expression {
-    pub fn make_binop_compatible(
-        op_type: BinaryOperationType,
-        left: Expression,
-        right: Expression,
-        scope: &Scope<DynoType>,
-    ) -> DynoResult<Option<Expression>> {
-        match op_type {
-            BinaryOperationType::Add
-            | BinaryOperationType::Subtract
-            | BinaryOperationType::Multiply
-            | BinaryOperationType::Divide
-            | BinaryOperationType::Equal
-            | BinaryOperationType::NotEqual
-            | BinaryOperationType::LessThan
-            | BinaryOperationType::LessThanEqual
-            | BinaryOperationType::GreaterThan
-            | BinaryOperationType::GreaterThanEqual => {
-                let left_type = left.get_type(scope)?;
-                let right_type = right.get_type(scope)?;
-                let left_size = left_type.get_bits();
-                let right_size = right_type.get_bits();
-
-                Ok(Some(match left_size.cmp(&right_size) {
-                    Ordering::Less => Expression::BinaryOperation(
-                        op_type,
-                        Box::new(Expression::Widen(Box::new(left), right_type)),
-                        Box::new(right),
-                    ),
-                    Ordering::Greater => Expression::BinaryOperation(
-                        op_type,
-                        Box::new(left),
-                        Box::new(Expression::Widen(Box::new(right), left_type)),
-                    ),
-                    Ordering::Equal => {
-                        Expression::BinaryOperation(op_type, Box::new(left), Box::new(right))
-                    }
-                }))
+    /// Widens the expression to the given type, integer literals are re-typed instead of getting
+    /// wrapped in a `Widen` node.
+    pub fn widen(expression: Expression, value_type: DynoType) -> Expression {
+        match expression {
+            Expression::Literal(_, DynoValue::UInt(x)) if value_type.is_int() => {
+                Expression::Literal(value_type, DynoValue::UInt(x))
             }
+            _ => Expression::Widen(Box::new(expression), value_type),
         }
     }
 
+    /// Makes the operands of a binary operation the same type and returns the operation together
+    /// with its type, the types of the operands are passed in so they don't have to be inferred
+    /// again for every operator in a long expression.
+    pub fn make_binop_compatible(
+        op_type: BinaryOperationType,
+        (left, left_type): (Expression, DynoType),
+        (right, right_type): (Expression, DynoType),
+    ) -> DynoResult<(Expression, DynoType)> {
+        let (left, left_type) = Expression::narrow_literal(left, left_type, &right, right_type);
+        let (right, right_type) = Expression::narrow_literal(right, right_type, &left, left_type);
+
+        // operands which can't be widened are left alone, checking the operands rejects them
+        let (left, right, left_type, right_type) = if left_type == right_type {
+            (left, right, left_type, right_type)
+        } else if left_type.can_widen_to(&right_type) {
+            (
+                Expression::widen(left, right_type),
+                right,
+                right_type,
+                right_type,
+            )
+        } else if right_type.can_widen_to(&left_type) {
+            (
+                left,
+                Expression::widen(right, left_type),
+                left_type,
+                left_type,
+            )
+        } else {
+            (left, right, left_type, right_type)
+        };
+
+        let value_type = op_type.check_operands(left_type, right_type)?;
+        Ok((
+            Expression::BinaryOperation(op_type, Box::new(left), Box::new(right)),
+            value_type,
+        ))
+    }
+
     pub fn make_assignment_compatible(
         left_type: DynoType,
         right: Expression,
         scope: &Scope<DynoType>,
     ) -> DynoResult<Expression> {
         let right_type = right.get_type(scope)?;
-        let left_size = left_type.get_bits();
-        let right_size = right_type.get_bits();
-
-        match left_size.cmp(&right_size) {
-            Ordering::Greater => match right {
-                Expression::BinaryOperation(op_type, l, r) => Ok(Expression::BinaryOperation(
-                    op_type,
-                    Box::new(Expression::make_assignment_compatible(
-                        left_type, *l, scope,
-                    )?),
-                    Box::new(Expression::make_assignment_compatible(
-                        left_type, *r, scope,
-                    )?),
-                )),
-                Expression::Literal(_, _) => Ok(Expression::Widen(Box::new(right), left_type)),
-                Expression::Widen(e, _) => Ok(Expression::Widen(e, left_type)),
-                Expression::Identifier(_) => Ok(Expression::Widen(Box::new(right), left_type)),
-            },
-            Ordering::Less => Err(DynoError::IncompatibleTypeError(left_type, right_type)),
-            Ordering::Equal => Ok(right),
+
+        if right_type == left_type {
+            return Ok(right);
+        }
+
+        if right_type.can_widen_to(&left_type) {
+            return Ok(Expression::widen_operands(right, left_type));
+        }
+
+        // a literal which is too wide for its value still fits in a narrower integer
+        match right {
+            Expression::Literal(_, DynoValue::UInt(x))
+                if left_type.is_int() && left_type.fits(x) =>
+            {
+                Ok(Expression::Literal(left_type, DynoValue::UInt(x)))
+            }
+            _ => Err(DynoError::IncompatibleTypeError(
+                left_type, right_type, None,
+            )),
+        }
+    }
+
+    /// Widens an integer expression to a wider integer type, the operands of arithmetic get
+    /// widened instead of the result. The operands have the same type as the operation so their
+    /// types don't have to be inferred again.
+    fn widen_operands(expression: Expression, value_type: DynoType) -> Expression {
+        match expression {
+            Expression::BinaryOperation(op_type, l, r) => Expression::BinaryOperation(
+                op_type,
+                Box::new(Expression::widen_operands(*l, value_type)),
+                Box::new(Expression::widen_operands(*r, value_type)),
+            ),
+            Expression::Literal(_, _) => Expression::widen(expression, value_type),
+            Expression::Widen(e, _) => Expression::Widen(e, value_type),
+            Expression::Identifier(_)
+            | Expression::Block(_, _)
+            | Expression::Assign(_, _)
+            | Expression::Conditional(_, _, _)
+            | Expression::Cast(_, _, _)
+            | Expression::Call(_, _) => Expression::Widen(Box::new(expression), value_type),
+        }
+    }
+
+    /// Gives an integer literal the type of the other operand when it is narrower and the value
+    /// fits in it, literals can be wider than needed when the parser has a default integer type.
+    fn narrow_literal(
+        expression: Expression,
+        value_type: DynoType,
+        other: &Expression,
+        other_type: DynoType,
+    ) -> (Expression, DynoType) {
+        match expression {
+            Expression::Literal(_, DynoValue::UInt(x))
+                if !matches!(other, Expression::Literal(_, _))
+                    && other_type.is_int()
+                    && other_type.get_bits() < value_type.get_bits()
+                    && other_type.fits(x) =>
+            {
+                (
+                    Expression::Literal(other_type, DynoValue::UInt(x)),
+                    other_type,
+                )
+            }
+            _ => (expression, value_type),
         }
     }
 
     pub fn get_type(&self, scope: &Scope<DynoType>) -> DynoResult<DynoType> {
         match self {
             Expression::BinaryOperation(op, left, right) => {
-                use BinaryOperationType::*;
-
-                let left_type = left.get_type(scope)?;
-                let right_type = right.get_type(scope)?;
-                // TODO: this should probably get replaced by something better
-                match op {
-                    Equal | NotEqual | LessThan | LessThanEqual | GreaterThan
-                    | GreaterThanEqual => {
-                        if left_type == right_type {
-                            Ok(DynoType::Bool())
-                        } else {
-                            Err(DynoError::IncompatibleTypeError(left_type, right_type))
-                        }
-                    }
-                    _ => {
-                        if left_type.is_int()
-                            && right_type.is_int()
-                            && (left_type.get_bits() == right_type.get_bits())
-                        {
-                            Ok(left_type)
-                        } else {
-                            Err(DynoError::IncompatibleTypeError(left_type, right_type))
-                        }
-                    }
-                }
+                op.check_operands(left.get_type(scope)?, right.get_type(scope)?)
             }
             Expression::Literal(value_type, _) => Ok(*value_type),
             Expression::Widen(_, value_type) => Ok(*value_type),
-            Expression::Identifier(x) => scope.find(x),
+            Expression::Identifier(x) => scope.find(x).map_err(|_| {
+                DynoError::IdentifierError(format!(
+                    "Identifier `{}` not found while inferring the type of an expression",
+                    x
+                ))
+            }),
+            Expression::Block(statements, expression) => {
+                // the resulting expression can use variables declared inside of the block
+                let mut block_scope = scope.clone();
+                block_scope.push();
+                for statement in statements {
+                    if let Statement::Declaration(name, value_type) = statement.without_comments() {
+                        block_scope.insert(name, *value_type)?;
+                    }
+                }
+
+                expression.get_type(&block_scope)
+            }
+            Expression::Assign(name, _) => scope.find(name),
+            // the parser makes both arms the same type
+            Expression::Conditional(_, true_expression, _) => true_expression.get_type(scope),
+            Expression::Cast(_, value_type, _) => Ok(*value_type),
+            Expression::Call(builtin, arguments) => Ok(builtin.return_type(arguments)),
         }
     }
 }
 
+/// Calls `f` on `statement` and on every statement nested inside it.
+///
+/// Statements inside block expressions are not visited, use `walk_statement_exprs` to reach
+/// those through their expressions.
+pub fn walk<'a, F: FnMut(&'a Statement)>(statement: &'a Statement, f: &mut F) {
+    f(statement);
+    match statement {
+        Statement::If(_, true_statement, false_statement) => {
+            walk(true_statement, f);
+            if let Some(false_statement) = false_statement {
+                walk(false_statement, f);
+            }
+        }
+        Statement::While(_, body)
+        | Statement::DoWhile(body, _)
+        | Statement::Commented(_, body)
+        | Statement::Labeled(_, body) => walk(body, f),
+        Statement::Block(statements) => {
+            for statement in statements {
+                walk(statement, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Calls `f` on `expression` and on every expression nested inside it, including the
+/// expressions of statements inside block expressions.
+pub fn walk_expr<'a, F: FnMut(&'a Expression)>(expression: &'a Expression, f: &mut F) {
+    f(expression);
+    match expression {
+        Expression::BinaryOperation(_, left, right) => {
+            walk_expr(left, f);
+            walk_expr(right, f);
+        }
+        Expression::Widen(expression, _)
+        | Expression::Assign(_, expression)
+        | Expression::Cast(expression, _, _) => walk_expr(expression, f),
+        Expression::Block(statements, expression) => {
+            for statement in statements {
+                walk_statement_exprs(statement, f);
+            }
+            walk_expr(expression, f);
+        }
+        Expression::Conditional(condition, true_expression, false_expression) => {
+            walk_expr(condition, f);
+            walk_expr(true_expression, f);
+            walk_expr(false_expression, f);
+        }
+        Expression::Call(_, arguments) => {
+            for (argument, _) in arguments {
+                walk_expr(argument, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Calls `f` on every expression contained in `statement` or any statement nested inside it.
+pub fn walk_statement_exprs<'a, F: FnMut(&'a Expression)>(statement: &'a Statement, f: &mut F) {
+    walk(statement, &mut |statement| match statement {
+        Statement::Assignment(_, expression)
+        | Statement::If(expression, _, _)
+        | Statement::While(expression, _)
+        | Statement::DoWhile(_, expression)
+        | Statement::Return(expression)
+        | Statement::Assert(expression) => walk_expr(expression, f),
+        _ => {}
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +532,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bin_op_classification() {
+        for op in &[Add, Subtract, Multiply, Divide] {
+            assert!(op.is_arithmetic());
+            assert!(!op.is_comparison());
+            assert_eq!(op.result_type(DynoType::UInt16()), DynoType::UInt16());
+        }
+
+        for op in &[
+            Equal,
+            NotEqual,
+            LessThan,
+            LessThanEqual,
+            GreaterThan,
+            GreaterThanEqual,
+        ] {
+            assert!(op.is_comparison());
+            assert!(!op.is_arithmetic());
+            assert_eq!(op.result_type(DynoType::UInt16()), DynoType::Bool());
+        }
+    }
+
     #[test]
     fn test_bin_op_size() {
         let ast = BinaryOperation(
@@ -229,4 +570,40 @@ mod tests {
         assert!(ast_type.is_ok());
         assert_eq!(ast_type.unwrap(), DynoType::UInt8());
     }
+
+    #[test]
+    fn walk_count_binary_operations() -> DynoResult<()> {
+        let ast = crate::parser::parse(crate::lexer::lex(
+            "let a: u8; a = 1 + 2 * 3; while a < 10 { a = a + 1; } return { let b: u8; b = a; b - 1 };",
+        )?)?;
+
+        let mut count = 0;
+        walk_statement_exprs(&ast, &mut |expression| {
+            if let BinaryOperation(..) = expression {
+                count += 1;
+            }
+        });
+        assert_eq!(count, 5);
+
+        let mut statements = 0;
+        walk(&ast, &mut |_| statements += 1);
+        assert_eq!(statements, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_identifier_type() -> DynoResult<()> {
+        let expected = "Identifier `b` not found while inferring the type of an expression";
+
+        match Expression::Identifier("b".to_string()).get_type(&Scope::default()) {
+            Err(DynoError::IdentifierError(message)) => assert_eq!(message, expected),
+            x => panic!("expected an identifier error, got {:?}", x),
+        }
+
+        match crate::parser::parse(crate::lexer::lex("let a: u8; a = b + 1;")?) {
+            Err(DynoError::IdentifierError(message)) => assert_eq!(message, expected),
+            x => panic!("expected an identifier error, got {:?}", x),
+        }
+        Ok(())
+    }
 }
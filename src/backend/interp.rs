@@ -0,0 +1,320 @@
+use crate::ast::{BinaryOperationType, Builtin, CastType, Expression, Statement};
+use crate::backend::Backend;
+use crate::error::{DynoError, DynoResult};
+use crate::scope::Scope;
+use crate::types::DynoType;
+use std::io::Read;
+
+/// A `break` or `continue` which is on its way to the loop it targets.
+enum LoopJump {
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+/// A tree-walking interpreter which directly evaluates the ast without generating any code.
+pub struct Interpreter {
+    variables: Scope<(DynoType, u64)>,
+    result: Option<u64>,
+    jump: Option<LoopJump>,
+}
+
+impl Backend for Interpreter {
+    /// Every value carries its type, so operations can wrap around at the size of the type.
+    type Register = (DynoType, u64);
+
+    fn generate_statement(&mut self, statement: &Statement) -> DynoResult<()> {
+        // nothing gets executed anymore after a return, or until a jump reaches its loop
+        if self.result.is_some() || self.jump.is_some() {
+            return Ok(());
+        }
+
+        match statement {
+            Statement::If(condition, true_statement, false_statement) => {
+                if self.generate_expression(condition)?.1 != 0 {
+                    self.generate_statement(true_statement)?;
+                } else if let Some(false_statement) = false_statement {
+                    self.generate_statement(false_statement)?;
+                }
+                Ok(())
+            }
+            Statement::While(condition, body) => self.run_loop(condition, body, None, true),
+            Statement::DoWhile(body, condition) => self.run_loop(condition, body, None, false),
+            Statement::Labeled(label, statement) => match statement.as_ref() {
+                Statement::While(condition, body) => {
+                    self.run_loop(condition, body, Some(label), true)
+                }
+                Statement::DoWhile(body, condition) => {
+                    self.run_loop(condition, body, Some(label), false)
+                }
+                _ => self.generate_statement(statement),
+            },
+            Statement::Break(label) => {
+                self.jump = Some(LoopJump::Break(label.clone()));
+                Ok(())
+            }
+            Statement::Continue(label) => {
+                self.jump = Some(LoopJump::Continue(label.clone()));
+                Ok(())
+            }
+            Statement::Return(expression) => {
+                self.result = Some(self.generate_expression(expression)?.1);
+                Ok(())
+            }
+            Statement::Assert(condition) => match self.generate_expression(condition)?.1 {
+                0 => Err(DynoError::GeneratorError(
+                    "runtime fault: assertion failed".to_string(),
+                )),
+                _ => Ok(()),
+            },
+            Statement::Commented(_, statement) => self.generate_statement(statement),
+            Statement::Block(children) => {
+                self.variables.push();
+                for child in children {
+                    self.generate_statement(child)?;
+                }
+                self.variables.pop()
+            }
+            Statement::Declaration(name, value_type) => {
+                self.variables.insert(name, (*value_type, 0))
+            }
+            Statement::Constant(_, _, _) => Ok(()),
+            Statement::Assignment(name, expression) => {
+                self.assign(name, expression)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn generate_expression(&mut self, expression: &Expression) -> DynoResult<Self::Register> {
+        match expression {
+            Expression::BinaryOperation(op_type, left, right) => {
+                let (left_type, left) = self.generate_expression(left)?;
+                let (_, right) = self.generate_expression(right)?;
+                let value = evaluate_binop(*op_type, left, right)?;
+
+                // the parser makes both operands the same type
+                let value_type = op_type.result_type(left_type);
+                Ok((value_type, value_type.wrap(value)))
+            }
+            Expression::Literal(value_type, value) => Ok((*value_type, value.to_bits())),
+            Expression::Widen(expression, value_type) => {
+                Ok((*value_type, self.generate_expression(expression)?.1))
+            }
+            Expression::Identifier(name) => self.variables.find(name),
+            Expression::Block(statements, expression) => {
+                self.variables.push();
+                for statement in statements {
+                    self.generate_statement(statement)?;
+                }
+                let value = self.generate_expression(expression)?;
+                self.variables.pop()?;
+                Ok(value)
+            }
+            Expression::Assign(name, expression) => self.assign(name, expression),
+            Expression::Cast(expression, value_type, cast_type) => {
+                let value = self.generate_expression(expression)?.1;
+                let value = match cast_type {
+                    CastType::Truncate => value_type.wrap(value),
+                    CastType::Saturate => value.min(value_type.wrap(u64::MAX)),
+                };
+                Ok((*value_type, value))
+            }
+            Expression::Conditional(condition, true_expression, false_expression) => {
+                match self.generate_expression(condition)?.1 {
+                    0 => self.generate_expression(false_expression),
+                    _ => self.generate_expression(true_expression),
+                }
+            }
+            Expression::Call(Builtin::ReadInt, _) => Ok((DynoType::UInt64(), read_int())),
+            Expression::Call(builtin, arguments) if builtin.is_method() => {
+                let value_type = arguments[0].1;
+                let left = value_type.wrap(self.generate_expression(&arguments[0].0)?.1) as u128;
+                let right = value_type.wrap(self.generate_expression(&arguments[1].0)?.1) as u128;
+                let max = value_type.wrap(u64::MAX) as u128;
+
+                let result = match builtin {
+                    Builtin::WrappingAdd => value_type.wrap((left + right) as u64),
+                    Builtin::WrappingSub => value_type.wrap(left.wrapping_sub(right) as u64),
+                    Builtin::WrappingMul => value_type.wrap((left * right) as u64),
+                    Builtin::SaturatingAdd => (left + right).min(max) as u64,
+                    Builtin::SaturatingSub => left.saturating_sub(right) as u64,
+                    _ => (left * right).min(max) as u64,
+                };
+                Ok((value_type, result))
+            }
+            Expression::Call(builtin, arguments) => {
+                let (argument, value_type) = &arguments[0];
+                let value = self.generate_expression(argument)?.1;
+                let bits = value_type.get_bits() as u32;
+
+                let result = match builtin {
+                    Builtin::CountOnes => value.count_ones(),
+                    Builtin::LeadingZeros => value.leading_zeros() - (64 - bits),
+                    _ => value.trailing_zeros().min(bits),
+                };
+                Ok((builtin.return_type(arguments), result as u64))
+            }
+        }
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            variables: Scope::new(),
+            result: None,
+            jump: None,
+        }
+    }
+}
+
+impl Interpreter {
+    /// Runs a loop, the condition is checked before the first iteration when `check_first` is set.
+    fn run_loop(
+        &mut self,
+        condition: &Expression,
+        body: &Statement,
+        label: Option<&str>,
+        check_first: bool,
+    ) -> DynoResult<()> {
+        let mut first = true;
+        while self.result.is_none()
+            && ((first && !check_first) || self.generate_expression(condition)?.1 != 0)
+        {
+            first = false;
+
+            self.generate_statement(body)?;
+
+            let jump = match self.jump.take() {
+                Some(jump) => jump,
+                None => continue,
+            };
+            let (is_break, target) = match &jump {
+                LoopJump::Break(target) => (true, target.as_deref()),
+                LoopJump::Continue(target) => (false, target.as_deref()),
+            };
+
+            // a jump to a labeled outer loop passes through this one
+            if target.is_some() && target != label {
+                self.jump = Some(jump);
+                break;
+            }
+
+            if is_break {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(&mut self, name: &str, expression: &Expression) -> DynoResult<(DynoType, u64)> {
+        let (value_type, _) = self.variables.find(name)?;
+        let value = value_type.wrap(self.generate_expression(expression)?.1);
+        self.variables.update(name, (value_type, value))?;
+        Ok((value_type, value))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads the next whitespace separated word from stdin, like `scanf("%lu")` does in the x86
+/// backend.
+fn read_int() -> u64 {
+    let mut word = String::new();
+    for byte in std::io::stdin().lock().bytes() {
+        match byte {
+            Ok(x) if x.is_ascii_whitespace() && word.is_empty() => {}
+            Ok(x) if !x.is_ascii_whitespace() => word.push(x as char),
+            _ => break,
+        }
+    }
+
+    word.parse().unwrap_or(0)
+}
+
+fn evaluate_binop(op_type: BinaryOperationType, left: u64, right: u64) -> DynoResult<u64> {
+    use BinaryOperationType::*;
+
+    let value = match op_type {
+        Add => left.wrapping_add(right),
+        Subtract => left.wrapping_sub(right),
+        Multiply => left.wrapping_mul(right),
+        Divide => match left.checked_div(right) {
+            Some(value) => value,
+            None => return Err(DynoError::GeneratorError("division by zero".to_string())),
+        },
+        Equal => (left == right) as u64,
+        NotEqual => (left != right) as u64,
+        LessThan => (left < right) as u64,
+        LessThanEqual => (left <= right) as u64,
+        GreaterThan => (left > right) as u64,
+        GreaterThanEqual => (left >= right) as u64,
+    };
+
+    Ok(value)
+}
+
+/// Interprets the ast and returns the value of the first executed return statement.
+///
+/// A program without a return statement results in 0.
+pub fn run(ast: &Statement) -> DynoResult<u64> {
+    let mut interpreter = Interpreter::new();
+    interpreter.generate_statement(ast)?;
+    Ok(interpreter.result.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lex;
+    use crate::parser::parse;
+
+    fn interpret(input: &str) -> DynoResult<u64> {
+        run(&parse(lex(input)?)?)
+    }
+
+    #[test]
+    fn interp_return_stops_execution() -> DynoResult<()> {
+        assert_eq!(interpret("return 1; return 2;")?, 1);
+        assert_eq!(
+            interpret("let a: u8; a = 0; while true { a = a + 1; if a == 3 { return a; } }")?,
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interp_limits() -> DynoResult<()> {
+        assert_eq!(interpret("let x: u8; x = 255 + 1; return x;")?, 0);
+        assert_eq!(interpret("let x: u8; x = 0 - 1; return x;")?, 255);
+        assert_eq!(interpret("let x: u16; x = 65535 + 1; return x;")?, 0);
+        assert_eq!(interpret("let x: u16; x = 0 - 1; return x;")?, 65535);
+        Ok(())
+    }
+
+    #[test]
+    fn interp_wraps_operations() -> DynoResult<()> {
+        assert_eq!(interpret("return 255 + 1;")?, 0);
+        assert_eq!(interpret("return (200 + 100) / 2;")?, 22);
+        assert_eq!(interpret("let x: u16; x = 200 + 100; return x;")?, 300);
+        Ok(())
+    }
+
+    #[test]
+    fn interp_division_by_zero() {
+        match interpret("return 5 / 0;") {
+            Err(DynoError::GeneratorError(message)) => assert_eq!(message, "division by zero"),
+            result => panic!("expected a generator error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn interp_without_return() -> DynoResult<()> {
+        assert_eq!(interpret("let a: u8; a = 4;")?, 0);
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+pub mod interp;
 pub mod x86_backend;
 
 use crate::ast::{Expression, Statement};
@@ -9,4 +10,9 @@ pub trait Backend {
     fn generate_statement(&mut self, statement: &Statement) -> DynoResult<()>;
 
     fn generate_expression(&mut self, expression: &Expression) -> DynoResult<Self::Register>;
+
+    /// Finalizes the output once every statement is generated, this does nothing by default.
+    fn finish(&mut self) -> DynoResult<()> {
+        Ok(())
+    }
 }
@@ -1,38 +1,97 @@
-use crate::ast::{BinaryOperationType, Expression, Statement};
+use crate::ast::{
+    walk_expr, walk_statement_exprs, BinaryOperationType, Builtin, CastType, Expression, Statement,
+};
 use crate::backend::Backend;
+use crate::checker;
 use crate::error::{DynoError, DynoResult};
+use crate::scope::Scope;
 use crate::types::{DynoType, DynoValue};
 use std::fs::File;
 use std::io::BufWriter;
+use std::io::ErrorKind;
 use std::io::Write;
-use std::process::Command;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus, Stdio};
 use std::time::SystemTime;
 
-const REG_NAMES: [&str; 4] = ["%r8", "%r9", "%r10", "%r11"];
+const REG_NAMES: [&str; 4] = ["r8", "r9", "r10", "r11"];
+const REG_NAMES_32: [&str; 4] = ["r8d", "r9d", "r10d", "r11d"];
+const REG_NAMES_16: [&str; 4] = ["r8w", "r9w", "r10w", "r11w"];
+const REG_NAMES_8: [&str; 4] = ["r8b", "r9b", "r10b", "r11b"];
+/// The registers holding the first integer arguments of a function in the System V calling
+/// convention.
+const ARGUMENT_REGS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
 
-pub struct X86Backend {
-    writer: BufWriter<File>,
+/// The assembly dialect emitted by the x86 backend.
+///
+/// `Att` is understood by the GNU assembler behind `cc`, `Intel` targets NASM.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsmSyntax {
+    Att,
+    Intel,
+}
+
+/// An instruction operand, formatted according to the selected syntax.
+#[derive(Debug, Clone, Copy)]
+enum Operand<'a> {
+    /// A scratch register accessed with the given amount of bits.
+    Reg(Register, u8),
+    /// A fixed register like rax or rbp.
+    Fixed(&'a str),
+    Immediate(u64),
+    /// A stack slot at the given offset below rbp, accessed with the given amount of bits.
+    Local(usize, u8),
+}
+
+/// Generates assembly for a program and writes it to `W`, compiling is left to the caller.
+pub struct X86Backend<W: Write> {
+    writer: W,
     regs: [bool; 4],
+    variables: Scope<(usize, DynoType)>,
+    stack_size: usize,
+    label_counter: usize,
+    /// Starts every label, this keeps the labels of functions written to the same file apart.
+    label_prefix: String,
+    /// Whether `read_int` is used, its format string is only written when it is.
+    reads_input: bool,
+    /// The label, condition label and exit label of every loop around the current statement.
+    loops: Vec<(Option<String>, String, String)>,
+    checked: bool,
+    minimize_registers: bool,
+    syntax: AsmSyntax,
 }
 
 type Register = usize;
 
-impl Backend for X86Backend {
+impl<W: Write> Backend for X86Backend<W> {
     type Register = Register;
 
     fn generate_statement(&mut self, statement: &Statement) -> DynoResult<()> {
         match statement {
-            Statement::If(condition, true_statement) => self.generate_if(condition, true_statement),
-            Statement::While(condition, body) => self.generate_while(condition, body),
-            Statement::Return(x) => self.generate_return(x),
-            Statement::Block(children) => {
-                for child in children {
-                    self.generate_statement(&child)?;
-                }
-                Ok(())
+            Statement::If(condition, true_statement, false_statement) => {
+                self.generate_if(condition, true_statement, false_statement.as_deref())
             }
+            Statement::While(condition, body) => self.generate_while(condition, body, None),
+            Statement::DoWhile(body, condition) => self.generate_do_while(body, condition, None),
+            Statement::Labeled(label, statement) => match statement.as_ref() {
+                Statement::While(condition, body) => {
+                    self.generate_while(condition, body, Some(label))
+                }
+                Statement::DoWhile(body, condition) => {
+                    self.generate_do_while(body, condition, Some(label))
+                }
+                _ => self.generate_statement(statement),
+            },
+            Statement::Break(label) => self.generate_loop_jump(label.as_deref(), true),
+            Statement::Continue(label) => self.generate_loop_jump(label.as_deref(), false),
+            Statement::Return(x) => self.generate_return(x),
+            Statement::Block(children) => self.generate_block(children),
             Statement::Declaration(name, value_type) => self.generate_declaration(name, value_type),
+            // references to constants are already replaced by their value
+            Statement::Constant(_, _, _) => Ok(()),
             Statement::Assignment(name, expression) => self.generate_assignment(name, expression),
+            Statement::Assert(condition) => self.generate_assert(condition),
+            Statement::Commented(_, statement) => self.generate_statement(statement),
         }
     }
 
@@ -46,18 +105,122 @@ impl Backend for X86Backend {
                 self.generate_widen(expression, value_type)
             }
             Expression::Identifier(name) => self.generate_identifier(name),
+            Expression::Block(statements, expression) => {
+                self.generate_block_expression(statements, expression)
+            }
+            Expression::Assign(name, expression) => self.generate_assign(name, expression),
+            Expression::Conditional(condition, true_expression, false_expression) => {
+                self.generate_conditional(condition, true_expression, false_expression)
+            }
+            Expression::Cast(expression, value_type, cast_type) => {
+                self.generate_cast(expression, value_type, *cast_type)
+            }
+            Expression::Call(builtin, arguments) => self.generate_call(*builtin, arguments),
         }
     }
+
+    /// Writes the epilogue which prints the result of the program and flushes the writer.
+    fn finish(&mut self) -> DynoResult<()> {
+        // Programs without a trailing return would otherwise fall off the end of main
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(0), Operand::Fixed("rsi")],
+        )?;
+
+        // every return jumps here with the result in rsi, the result gets printed so the exit
+        // code only has to signal whether the program ran successfully
+        writeln!(self.writer, "{}:", self.label("return"))?;
+        // the stack has to be 16 byte aligned when calling into libc
+        let format = self.label("format");
+        match self.syntax {
+            AsmSyntax::Att => {
+                writeln!(self.writer, "leaq {}(%rip), %rdi", format)?;
+                writeln!(self.writer, "andq $-16, %rsp")?;
+            }
+            AsmSyntax::Intel => {
+                writeln!(self.writer, "lea rdi, [rel {}]", format)?;
+                writeln!(self.writer, "and rsp, -16")?;
+            }
+        }
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(0), Operand::Fixed("rax")],
+        )?;
+        match self.syntax {
+            AsmSyntax::Att => writeln!(self.writer, "call printf@PLT")?,
+            AsmSyntax::Intel => writeln!(self.writer, "call printf wrt ..plt")?,
+        }
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(0), Operand::Fixed("rax")],
+        )?;
+        writeln!(self.writer, "leave")?;
+        writeln!(self.writer, "ret")?;
+
+        self.generate_rodata(true)?;
+        self.writer.flush()?;
+        Ok(())
+    }
 }
 
-impl X86Backend {
-    fn new(file_name: &str) -> Self {
+impl<W: Write> X86Backend<W> {
+    /// Writes the format string for `printf` when the result gets printed, and the one for
+    /// `scanf` when `read_int` is used.
+    fn generate_rodata(&mut self, prints_result: bool) -> DynoResult<()> {
+        if !prints_result && !self.reads_input {
+            return Ok(());
+        }
+
+        match self.syntax {
+            AsmSyntax::Att => writeln!(self.writer, ".section .rodata")?,
+            AsmSyntax::Intel => writeln!(self.writer, "section .rodata")?,
+        }
+        if prints_result {
+            writeln!(self.writer, "{}:", self.label("format"))?;
+            match self.syntax {
+                AsmSyntax::Att => writeln!(self.writer, ".string \"%lu\\n\"")?,
+                AsmSyntax::Intel => writeln!(self.writer, "db \"%lu\", 10, 0")?,
+            }
+        }
+        if self.reads_input {
+            writeln!(self.writer, "{}:", self.label("read_format"))?;
+            match self.syntax {
+                AsmSyntax::Att => writeln!(self.writer, ".string \"%lu\"")?,
+                AsmSyntax::Intel => writeln!(self.writer, "db \"%lu\", 0")?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn new(writer: W, config: &X86Config) -> Self {
         Self {
-            writer: BufWriter::new(File::create(file_name).unwrap()),
+            writer,
             regs: [false; 4],
+            variables: Scope::new(),
+            stack_size: 0,
+            label_counter: 0,
+            label_prefix: String::new(),
+            reads_input: false,
+            loops: vec![],
+            checked: config.checked,
+            minimize_registers: config.minimize_registers,
+            syntax: config.syntax,
         }
     }
 
+    fn fresh_label(&mut self, prefix: &str) -> String {
+        self.label_counter += 1;
+        format!("{}_{}", self.label(prefix), self.label_counter)
+    }
+
+    /// Returns the local label with the given name.
+    fn label(&self, name: &str) -> String {
+        format!(".L{}{}", self.label_prefix, name)
+    }
+
     fn allocate_reg(&mut self) -> DynoResult<Register> {
         for (i, reg) in self.regs.iter().enumerate() {
             if !reg {
@@ -82,15 +245,139 @@ impl X86Backend {
         Ok(())
     }
 
-    fn finish(&mut self) -> DynoResult<()> {
-        self.writer.flush()?;
+    fn format_operand(&self, operand: Operand) -> String {
+        let reg_name = |reg: Register, bits| match bits {
+            8 => REG_NAMES_8[reg],
+            16 => REG_NAMES_16[reg],
+            32 => REG_NAMES_32[reg],
+            _ => REG_NAMES[reg],
+        };
+
+        match (self.syntax, operand) {
+            (AsmSyntax::Att, Operand::Reg(reg, bits)) => format!("%{}", reg_name(reg, bits)),
+            (AsmSyntax::Att, Operand::Fixed(name)) => format!("%{}", name),
+            (AsmSyntax::Att, Operand::Immediate(x)) => format!("${}", x),
+            (AsmSyntax::Att, Operand::Local(offset, _)) => format!("-{}(%rbp)", offset),
+            (AsmSyntax::Intel, Operand::Reg(reg, bits)) => reg_name(reg, bits).to_string(),
+            (AsmSyntax::Intel, Operand::Fixed(name)) => name.to_string(),
+            (AsmSyntax::Intel, Operand::Immediate(x)) => x.to_string(),
+            (AsmSyntax::Intel, Operand::Local(offset, bits)) => {
+                let size = match bits {
+                    8 => "byte",
+                    16 => "word",
+                    32 => "dword",
+                    _ => "qword",
+                };
+                format!("{} [rbp - {}]", size, offset)
+            }
+        }
+    }
+
+    /// Writes a single instruction, the operands are given in AT&T order (source first).
+    fn emit(&mut self, att: &str, intel: &str, operands: &[Operand]) -> DynoResult<()> {
+        let mut operands: Vec<String> = operands.iter().map(|x| self.format_operand(*x)).collect();
+        let mnemonic = match self.syntax {
+            AsmSyntax::Att => att,
+            AsmSyntax::Intel => {
+                operands.reverse();
+                intel
+            }
+        };
+
+        if operands.is_empty() {
+            writeln!(self.writer, "{}", mnemonic)?;
+        } else {
+            writeln!(self.writer, "{} {}", mnemonic, operands.join(", "))?;
+        }
         Ok(())
     }
 
-    fn generate_header(&mut self) -> DynoResult<()> {
-        writeln!(self.writer, ".globl main")?;
-        writeln!(self.writer, ".text")?;
-        writeln!(self.writer, "main:")?;
+    /// Writes the directives and the prologue of `main` for the program, this has to come before
+    /// any statement.
+    pub fn generate_header(&mut self, ast: &Statement) -> DynoResult<()> {
+        self.generate_function_header("main", frame_size(ast, &[])?)
+    }
+
+    /// Writes the directives and a prologue which reserves `frame_size` bytes for the variables.
+    fn generate_function_header(&mut self, name: &str, frame_size: usize) -> DynoResult<()> {
+        // mark the stack as non-executable, otherwise the linker assumes it is executable
+        match self.syntax {
+            AsmSyntax::Att => {
+                writeln!(self.writer, ".globl {}", name)?;
+                writeln!(self.writer, ".section .note.GNU-stack,\"\",@progbits")?;
+                writeln!(self.writer, ".text")?;
+            }
+            AsmSyntax::Intel => {
+                writeln!(self.writer, "global {}", name)?;
+                writeln!(self.writer, "extern printf")?;
+                writeln!(self.writer, "extern scanf")?;
+                writeln!(
+                    self.writer,
+                    "section .note.GNU-stack noalloc noexec nowrite progbits"
+                )?;
+                writeln!(self.writer, "section .text")?;
+            }
+        }
+        writeln!(self.writer, "{}:", name)?;
+        self.emit("pushq", "push", &[Operand::Fixed("rbp")])?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Fixed("rsp"), Operand::Fixed("rbp")],
+        )?;
+        if frame_size > 0 {
+            self.emit(
+                "subq",
+                "sub",
+                &[Operand::Immediate(frame_size as u64), Operand::Fixed("rsp")],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Stores the arguments passed in the System V argument registers in the parameters.
+    fn generate_parameters(&mut self, params: &[(String, DynoType)]) -> DynoResult<()> {
+        if params.len() > ARGUMENT_REGS.len() {
+            return Err(DynoError::GeneratorError(format!(
+                "Functions can have at most {} parameters",
+                ARGUMENT_REGS.len()
+            )));
+        }
+
+        for ((name, value_type), argument) in params.iter().zip(ARGUMENT_REGS.iter()) {
+            self.generate_declaration(name, value_type)?;
+            // loads only read the bits of the type, so the whole register can be stored
+            self.emit(
+                "movq",
+                "mov",
+                &[
+                    Operand::Fixed(argument),
+                    Operand::Local(self.stack_size, 64),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the epilogue of a function which returns its result in rax.
+    fn generate_function_footer(&mut self) -> DynoResult<()> {
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(0), Operand::Fixed("rsi")],
+        )?;
+
+        writeln!(self.writer, "{}:", self.label("return"))?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Fixed("rsi"), Operand::Fixed("rax")],
+        )?;
+        writeln!(self.writer, "leave")?;
+        writeln!(self.writer, "ret")?;
+
+        self.generate_rodata(false)?;
+        self.writer.flush()?;
         Ok(())
     }
 
@@ -102,35 +389,83 @@ impl X86Backend {
     ) -> DynoResult<Register> {
         use BinaryOperationType::*;
 
-        println!("{:#?}\n{:#?}", left, right);
+        // evaluating the operand which needs the most registers first keeps fewer registers live,
+        // this is only done when it can't change the order of side effects
+        let right_first = self.minimize_registers
+            && register_need(right) > register_need(left)
+            && !has_side_effects(left)
+            && !has_side_effects(right);
 
-        let left = self.generate_expression(left)?;
-        let right = self.generate_expression(right)?;
-
-        println!("{} {}", left, right);
+        let value_type = expression_type(left, &self.variables)?;
+        let (left, right) = if right_first {
+            let right = self.generate_expression(right)?;
+            (self.generate_expression(left)?, right)
+        } else {
+            let left = self.generate_expression(left)?;
+            (left, self.generate_expression(right)?)
+        };
+        let operands = [Operand::Reg(right, 64), Operand::Reg(left, 64)];
 
+        // registers always hold a value which fits in its type, so the results of arithmetic are
+        // truncated to make them wrap around like they do in the interpreter
         match op_type {
-            Add => writeln!(
-                self.writer,
-                "addq {}, {}",
-                REG_NAMES[right], REG_NAMES[left]
-            )?,
-            Subtract => writeln!(
-                self.writer,
-                "subq {}, {}",
-                REG_NAMES[right], REG_NAMES[left]
-            )?,
-            Multiply => writeln!(
-                self.writer,
-                "imul {}, {}",
-                REG_NAMES[right], REG_NAMES[left]
-            )?,
-            Divide => writeln!(
-                self.writer,
-                "movq {}, %rax\nmovq $0, %rdx\ndivq {}\nmovq %rax, {}",
-                REG_NAMES[left], REG_NAMES[right], REG_NAMES[left]
-            )?,
-            _ => todo!(),
+            Add => {
+                self.emit("addq", "add", &operands)?;
+                self.zero_extend(left, value_type.get_bits())?;
+            }
+            Subtract => {
+                self.emit("subq", "sub", &operands)?;
+
+                if self.checked {
+                    // the operands fit in their type so a borrow means the result underflowed
+                    let checked_label = self.fresh_label("checked");
+                    writeln!(self.writer, "jnc {}", checked_label)?;
+                    writeln!(self.writer, "ud2")?;
+                    writeln!(self.writer, "{}:", checked_label)?;
+                }
+                self.zero_extend(left, value_type.get_bits())?;
+            }
+            Multiply => {
+                self.emit("imul", "imul", &operands)?;
+                self.zero_extend(left, value_type.get_bits())?;
+            }
+            Divide => {
+                self.emit(
+                    "movq",
+                    "mov",
+                    &[Operand::Reg(left, 64), Operand::Fixed("rax")],
+                )?;
+                self.emit(
+                    "movq",
+                    "mov",
+                    &[Operand::Immediate(0), Operand::Fixed("rdx")],
+                )?;
+                self.emit("divq", "div", &[Operand::Reg(right, 64)])?;
+                self.emit(
+                    "movq",
+                    "mov",
+                    &[Operand::Fixed("rax"), Operand::Reg(left, 64)],
+                )?;
+            }
+            Equal | NotEqual | LessThan | LessThanEqual | GreaterThan | GreaterThanEqual => {
+                let instruction = match op_type {
+                    Equal => "sete",
+                    NotEqual => "setne",
+                    LessThan => "setb",
+                    LessThanEqual => "setbe",
+                    GreaterThan => "seta",
+                    _ => "setae",
+                };
+
+                self.emit("cmpq", "cmp", &operands)?;
+                self.emit(instruction, instruction, &[Operand::Reg(left, 8)])?;
+                // setcc only writes the lowest byte, clear the rest of the register
+                self.emit(
+                    "movzbq",
+                    "movzx",
+                    &[Operand::Reg(left, 8), Operand::Reg(left, 64)],
+                )?;
+            }
         }
         self.deallocate_reg(right)?;
         Ok(left)
@@ -138,22 +473,16 @@ impl X86Backend {
 
     fn generate_literal(
         &mut self,
-        value_type: &DynoType,
+        _value_type: &DynoType,
         value: &DynoValue,
     ) -> DynoResult<Register> {
-        use crate::types::DynoValue::*;
-
         let reg = self.allocate_reg()?;
 
-        match (value_type, value) {
-            (_, UInt(x)) => writeln!(self.writer, "movq ${}, {}", x, REG_NAMES[reg])?,
-            _ => {
-                return Err(DynoError::GeneratorError(format!(
-                    "Failed to generate literal for {:?}, {:?}",
-                    value_type, value,
-                )))
-            }
-        }
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(value.to_bits()), Operand::Reg(reg, 64)],
+        )?;
 
         Ok(reg)
     }
@@ -167,79 +496,1139 @@ impl X86Backend {
         self.generate_expression(expression)
     }
 
-    fn generate_identifier(&mut self, _name: &str) -> DynoResult<Register> {
-        Ok(0)
+    /// Clears the bits of the register above the given width.
+    fn zero_extend(&mut self, reg: Register, bits: u8) -> DynoResult<()> {
+        match bits {
+            8 => self.emit(
+                "movzbq",
+                "movzx",
+                &[Operand::Reg(reg, 8), Operand::Reg(reg, 64)],
+            ),
+            16 => self.emit(
+                "movzwq",
+                "movzx",
+                &[Operand::Reg(reg, 16), Operand::Reg(reg, 64)],
+            ),
+            32 => self.emit(
+                "movl",
+                "mov",
+                &[Operand::Reg(reg, 32), Operand::Reg(reg, 32)],
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn generate_cast(
+        &mut self,
+        expression: &Expression,
+        value_type: &DynoType,
+        cast_type: CastType,
+    ) -> DynoResult<Register> {
+        let reg = self.generate_expression(expression)?;
+        let bits = value_type.get_bits();
+        if bits >= 64 {
+            // values are always zero extended so widening doesn't change the register
+            return Ok(reg);
+        }
+
+        match cast_type {
+            CastType::Truncate => self.zero_extend(reg, bits)?,
+            CastType::Saturate => self.clamp_to_max(reg, *value_type)?,
+        }
+
+        Ok(reg)
+    }
+
+    /// Replaces the value in `reg` by `value` when the last instruction set the carry flag.
+    fn saturate_on_carry(&mut self, reg: Register, value: u64) -> DynoResult<()> {
+        let saturated_label = self.fresh_label("saturated");
+        writeln!(self.writer, "jnc {}", saturated_label)?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(value), Operand::Reg(reg, 64)],
+        )?;
+        writeln!(self.writer, "{}:", saturated_label)?;
+        Ok(())
+    }
+
+    /// Replaces the value in `reg` by the maximum of `value_type` when it is larger.
+    fn clamp_to_max(&mut self, reg: Register, value_type: DynoType) -> DynoResult<()> {
+        let saturated_label = self.fresh_label("saturated");
+        let max = value_type.wrap(u64::MAX);
+
+        // the maximum of a 32 bit type doesn't fit in a sign extended immediate
+        let max_reg = self.allocate_reg()?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(max), Operand::Reg(max_reg, 64)],
+        )?;
+        self.emit(
+            "cmpq",
+            "cmp",
+            &[Operand::Reg(max_reg, 64), Operand::Reg(reg, 64)],
+        )?;
+        writeln!(self.writer, "jbe {}", saturated_label)?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Reg(max_reg, 64), Operand::Reg(reg, 64)],
+        )?;
+        writeln!(self.writer, "{}:", saturated_label)?;
+        self.deallocate_reg(max_reg)
+    }
+
+    fn generate_identifier(&mut self, name: &str) -> DynoResult<Register> {
+        let (offset, value_type) = self.variables.find(name)?;
+        let reg = self.allocate_reg()?;
+        let bits = value_type.get_bits();
+        let local = Operand::Local(offset, bits);
+
+        // loads zero extend the value so the full register holds the value
+        match bits {
+            8 => self.emit("movzbq", "movzx", &[local, Operand::Reg(reg, 64)])?,
+            16 => self.emit("movzwq", "movzx", &[local, Operand::Reg(reg, 64)])?,
+            32 => self.emit("movl", "mov", &[local, Operand::Reg(reg, 32)])?,
+            _ => self.emit("movq", "mov", &[local, Operand::Reg(reg, 64)])?,
+        }
+
+        Ok(reg)
+    }
+
+    fn generate_block_expression(
+        &mut self,
+        statements: &[Statement],
+        expression: &Expression,
+    ) -> DynoResult<Register> {
+        self.variables.push();
+        for statement in statements {
+            self.generate_statement(statement)?;
+        }
+        let reg = self.generate_expression(expression)?;
+        self.pop_variables()?;
+
+        Ok(reg)
     }
 
     fn generate_if(
         &mut self,
-        _condition: &Expression,
-        _true_statement: &Statement,
+        condition: &Expression,
+        true_statement: &Statement,
+        false_statement: Option<&Statement>,
+    ) -> DynoResult<()> {
+        let else_label = self.fresh_label("else");
+        let continue_label = self.fresh_label("continue");
+
+        let condition = self.generate_expression(condition)?;
+        self.emit(
+            "cmpq",
+            "cmp",
+            &[Operand::Immediate(0), Operand::Reg(condition, 64)],
+        )?;
+        writeln!(self.writer, "je {}", else_label)?;
+        self.deallocate_reg(condition)?;
+
+        // both branches have to start from and end in the same register state
+        let regs = self.regs;
+
+        self.generate_statement(true_statement)?;
+        self.regs = regs;
+
+        if let Some(false_statement) = false_statement {
+            writeln!(self.writer, "jmp {}", continue_label)?;
+            writeln!(self.writer, "{}:", else_label)?;
+            self.generate_statement(false_statement)?;
+            self.regs = regs;
+        } else {
+            writeln!(self.writer, "{}:", else_label)?;
+        }
+
+        writeln!(self.writer, "{}:", continue_label)?;
+        Ok(())
+    }
+
+    fn generate_conditional(
+        &mut self,
+        condition: &Expression,
+        true_expression: &Expression,
+        false_expression: &Expression,
+    ) -> DynoResult<Register> {
+        let else_label = self.fresh_label("else");
+        let continue_label = self.fresh_label("continue");
+
+        let condition = self.generate_expression(condition)?;
+        self.emit(
+            "cmpq",
+            "cmp",
+            &[Operand::Immediate(0), Operand::Reg(condition, 64)],
+        )?;
+        writeln!(self.writer, "je {}", else_label)?;
+        self.deallocate_reg(condition)?;
+
+        let regs = self.regs;
+
+        let result = self.generate_expression(true_expression)?;
+        writeln!(self.writer, "jmp {}", continue_label)?;
+        writeln!(self.writer, "{}:", else_label)?;
+
+        // both arms have to leave their value in the same register
+        self.regs = regs;
+        let reg = self.generate_expression(false_expression)?;
+        if reg != result {
+            self.emit(
+                "movq",
+                "mov",
+                &[Operand::Reg(reg, 64), Operand::Reg(result, 64)],
+            )?;
+            self.deallocate_reg(reg)?;
+            self.regs[result] = true;
+        }
+
+        writeln!(self.writer, "{}:", continue_label)?;
+        Ok(result)
+    }
+
+    fn generate_while(
+        &mut self,
+        condition: &Expression,
+        body: &Statement,
+        label: Option<&str>,
     ) -> DynoResult<()> {
-        todo!();
+        let condition_label = self.fresh_label("while");
+        let continue_label = self.fresh_label("continue");
+
+        writeln!(self.writer, "{}:", condition_label)?;
+        let condition = self.generate_expression(condition)?;
+        self.emit(
+            "cmpq",
+            "cmp",
+            &[Operand::Immediate(0), Operand::Reg(condition, 64)],
+        )?;
+        writeln!(self.writer, "je {}", continue_label)?;
+        self.deallocate_reg(condition)?;
+
+        self.loops.push((
+            label.map(str::to_string),
+            condition_label.clone(),
+            continue_label.clone(),
+        ));
+        let body = self.generate_statement(body);
+        self.loops.pop();
+        body?;
+
+        writeln!(self.writer, "jmp {}", condition_label)?;
+        writeln!(self.writer, "{}:", continue_label)?;
+        Ok(())
     }
 
-    fn generate_while(&mut self, _condition: &Expression, _body: &Statement) -> DynoResult<()> {
-        todo!();
+    fn generate_do_while(
+        &mut self,
+        body: &Statement,
+        condition: &Expression,
+        label: Option<&str>,
+    ) -> DynoResult<()> {
+        let body_label = self.fresh_label("do");
+        let condition_label = self.fresh_label("while");
+        let continue_label = self.fresh_label("continue");
+
+        writeln!(self.writer, "{}:", body_label)?;
+        self.loops.push((
+            label.map(str::to_string),
+            condition_label.clone(),
+            continue_label.clone(),
+        ));
+        let body = self.generate_statement(body);
+        self.loops.pop();
+        body?;
+
+        writeln!(self.writer, "{}:", condition_label)?;
+        let condition = self.generate_expression(condition)?;
+        self.emit(
+            "cmpq",
+            "cmp",
+            &[Operand::Immediate(0), Operand::Reg(condition, 64)],
+        )?;
+        writeln!(self.writer, "jne {}", body_label)?;
+        self.deallocate_reg(condition)?;
+        writeln!(self.writer, "{}:", continue_label)?;
+        Ok(())
+    }
+
+    /// Jumps to the exit of the targeted loop for a `break`, or to its condition otherwise.
+    fn generate_loop_jump(&mut self, label: Option<&str>, is_break: bool) -> DynoResult<()> {
+        let (_, condition_label, exit_label) = self
+            .loops
+            .iter()
+            .rev()
+            .find(|(loop_label, _, _)| label.is_none() || loop_label.as_deref() == label)
+            .ok_or_else(|| DynoError::GeneratorError("Loop jump outside of a loop".to_string()))?;
+
+        let target = if is_break {
+            exit_label
+        } else {
+            condition_label
+        };
+        writeln!(self.writer, "jmp {}", target)?;
+        Ok(())
+    }
+
+    fn generate_assert(&mut self, condition: &Expression) -> DynoResult<()> {
+        let assert_label = self.fresh_label("assert");
+
+        let condition = self.generate_expression(condition)?;
+        self.emit(
+            "cmpq",
+            "cmp",
+            &[Operand::Immediate(0), Operand::Reg(condition, 64)],
+        )?;
+        writeln!(self.writer, "jne {}", assert_label)?;
+        writeln!(self.writer, "ud2")?;
+        writeln!(self.writer, "{}:", assert_label)?;
+
+        self.deallocate_reg(condition)
     }
 
     fn generate_return(&mut self, expression: &Expression) -> DynoResult<()> {
         let reg = self.generate_expression(expression)?;
 
-        writeln!(self.writer, "movq {}, %rax", REG_NAMES[reg])?;
-        writeln!(self.writer, "ret")?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Reg(reg, 64), Operand::Fixed("rsi")],
+        )?;
+        writeln!(self.writer, "jmp {}", self.label("return"))?;
 
         self.deallocate_reg(reg)
     }
 
-    #[allow(dead_code)]
+    fn generate_call(
+        &mut self,
+        builtin: Builtin,
+        arguments: &[(Expression, DynoType)],
+    ) -> DynoResult<Register> {
+        match builtin {
+            Builtin::ReadInt => self.generate_read_int(),
+            _ if builtin.is_method() => self.generate_method(builtin, arguments),
+            _ => self.generate_bit_count(builtin, &arguments[0].0, arguments[0].1),
+        }
+    }
+
+    /// Generates a wrapping or saturating operation, the operands fit in their type so an overflow
+    /// of the type shows up in the 64 bit result or in the carry flag.
+    fn generate_method(
+        &mut self,
+        builtin: Builtin,
+        arguments: &[(Expression, DynoType)],
+    ) -> DynoResult<Register> {
+        use Builtin::*;
+
+        let value_type = arguments[0].1;
+        let bits = value_type.get_bits();
+
+        let left = self.generate_expression(&arguments[0].0)?;
+        let right = self.generate_expression(&arguments[1].0)?;
+        let operands = [Operand::Reg(right, 64), Operand::Reg(left, 64)];
+
+        match builtin {
+            WrappingAdd | SaturatingAdd => self.emit("addq", "add", &operands)?,
+            WrappingSub | SaturatingSub => self.emit("subq", "sub", &operands)?,
+            // the high half of a 64 bit product ends up in rdx and sets the carry flag
+            SaturatingMul if bits == 64 => {
+                self.emit(
+                    "movq",
+                    "mov",
+                    &[Operand::Reg(left, 64), Operand::Fixed("rax")],
+                )?;
+                self.emit("mulq", "mul", &[Operand::Reg(right, 64)])?;
+                self.emit(
+                    "movq",
+                    "mov",
+                    &[Operand::Fixed("rax"), Operand::Reg(left, 64)],
+                )?;
+            }
+            _ => self.emit("imul", "imul", &operands)?,
+        }
+        self.deallocate_reg(right)?;
+
+        match builtin {
+            SaturatingSub => self.saturate_on_carry(left, 0)?,
+            // the product of two 32 bit values still fits in 64 bits
+            SaturatingAdd | SaturatingMul if bits < 64 => self.clamp_to_max(left, value_type)?,
+            SaturatingAdd | SaturatingMul => self.saturate_on_carry(left, u64::MAX)?,
+            _ => self.zero_extend(left, bits)?,
+        }
+
+        Ok(left)
+    }
+
+    /// Counts bits within the width of the argument.
+    fn generate_bit_count(
+        &mut self,
+        builtin: Builtin,
+        argument: &Expression,
+        value_type: DynoType,
+    ) -> DynoResult<Register> {
+        let reg = self.generate_expression(argument)?;
+        let bits = value_type.get_bits();
+        let operands = [Operand::Reg(reg, 64), Operand::Reg(reg, 64)];
+
+        match builtin {
+            Builtin::CountOnes => self.emit("popcntq", "popcnt", &operands)?,
+            Builtin::LeadingZeros => {
+                self.emit("lzcntq", "lzcnt", &operands)?;
+                if bits < 64 {
+                    self.emit(
+                        "subq",
+                        "sub",
+                        &[Operand::Immediate(64 - bits as u64), Operand::Reg(reg, 64)],
+                    )?;
+                }
+            }
+            _ => {
+                // a bit right above the width limits the count for 0, a 32 bit count does that by
+                // itself
+                match bits {
+                    8 | 16 => self.emit(
+                        "orq",
+                        "or",
+                        &[Operand::Immediate(1 << bits), Operand::Reg(reg, 64)],
+                    )?,
+                    _ => {}
+                }
+                match bits {
+                    32 => self.emit(
+                        "tzcntl",
+                        "tzcnt",
+                        &[Operand::Reg(reg, 32), Operand::Reg(reg, 32)],
+                    )?,
+                    _ => self.emit("tzcntq", "tzcnt", &operands)?,
+                }
+            }
+        }
+
+        Ok(reg)
+    }
+
+    /// Calls `scanf` with a zeroed stack slot, so the result is 0 when no integer could be read.
+    fn generate_read_int(&mut self) -> DynoResult<Register> {
+        self.reads_input = true;
+
+        // the scratch registers are caller saved, so the ones in use have to survive the call
+        let live: Vec<Register> = (0..self.regs.len()).filter(|x| self.regs[*x]).collect();
+        for reg in &live {
+            self.emit("pushq", "push", &[Operand::Reg(*reg, 64)])?;
+        }
+
+        // align the stack for libc and remember the old stack pointer right above the slot
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Fixed("rsp"), Operand::Fixed("rax")],
+        )?;
+        match self.syntax {
+            AsmSyntax::Att => writeln!(self.writer, "andq $-16, %rsp")?,
+            AsmSyntax::Intel => writeln!(self.writer, "and rsp, -16")?,
+        }
+        self.emit("pushq", "push", &[Operand::Fixed("rax")])?;
+        self.emit("pushq", "push", &[Operand::Immediate(0)])?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Fixed("rsp"), Operand::Fixed("rsi")],
+        )?;
+        let format = self.label("read_format");
+        match self.syntax {
+            AsmSyntax::Att => writeln!(self.writer, "leaq {}(%rip), %rdi", format)?,
+            AsmSyntax::Intel => writeln!(self.writer, "lea rdi, [rel {}]", format)?,
+        }
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Immediate(0), Operand::Fixed("rax")],
+        )?;
+        match self.syntax {
+            AsmSyntax::Att => writeln!(self.writer, "call scanf@PLT")?,
+            AsmSyntax::Intel => writeln!(self.writer, "call scanf wrt ..plt")?,
+        }
+        self.emit("popq", "pop", &[Operand::Fixed("rax")])?;
+        self.emit("popq", "pop", &[Operand::Fixed("rsp")])?;
+
+        for reg in live.iter().rev() {
+            self.emit("popq", "pop", &[Operand::Reg(*reg, 64)])?;
+        }
+
+        let reg = self.allocate_reg()?;
+        self.emit(
+            "movq",
+            "mov",
+            &[Operand::Fixed("rax"), Operand::Reg(reg, 64)],
+        )?;
+        Ok(reg)
+    }
+
     fn generate_block(&mut self, children: &[Statement]) -> DynoResult<()> {
+        self.variables.push();
         for child in children {
             self.generate_statement(child)?;
         }
-        Ok(())
+        self.pop_variables()
+    }
+
+    /// Leaves the innermost scope, the stack slots of its variables can be used again.
+    fn pop_variables(&mut self) -> DynoResult<()> {
+        self.stack_size -= 8 * self.variables.current_names().len();
+        self.variables.pop()
     }
 
-    fn generate_declaration(&mut self, _name: &str, _value_type: &DynoType) -> DynoResult<()> {
-        todo!();
+    fn generate_declaration(&mut self, name: &str, value_type: &DynoType) -> DynoResult<()> {
+        // every variable gets its own 8 byte stack slot, the prologue reserves all of them
+        self.stack_size += 8;
+        self.variables.insert(name, (self.stack_size, *value_type))
     }
 
-    fn generate_assignment(&mut self, _name: &str, _expression: &Expression) -> DynoResult<()> {
-        todo!();
+    /// Stores the value of the expression in the variable, the stored value stays in the register.
+    fn generate_assign(&mut self, name: &str, expression: &Expression) -> DynoResult<Register> {
+        let (offset, value_type) = self.variables.find(name)?;
+        let reg = self.generate_expression(expression)?;
+        let bits = value_type.get_bits();
+        let local = Operand::Local(offset, bits);
+
+        // only store the lower bits, this makes the value wrap around at the type's size
+        match bits {
+            8 => {
+                self.emit("movb", "mov", &[Operand::Reg(reg, 8), local])?;
+                self.emit(
+                    "movzbq",
+                    "movzx",
+                    &[Operand::Reg(reg, 8), Operand::Reg(reg, 64)],
+                )?;
+            }
+            16 => {
+                self.emit("movw", "mov", &[Operand::Reg(reg, 16), local])?;
+                self.emit(
+                    "movzwq",
+                    "movzx",
+                    &[Operand::Reg(reg, 16), Operand::Reg(reg, 64)],
+                )?;
+            }
+            32 => {
+                self.emit("movl", "mov", &[Operand::Reg(reg, 32), local])?;
+                // writing a 32 bit register clears the upper half
+                self.emit(
+                    "movl",
+                    "mov",
+                    &[Operand::Reg(reg, 32), Operand::Reg(reg, 32)],
+                )?;
+            }
+            _ => self.emit("movq", "mov", &[Operand::Reg(reg, 64), local])?,
+        }
+
+        Ok(reg)
     }
+
+    fn generate_assignment(&mut self, name: &str, expression: &Expression) -> DynoResult<()> {
+        let reg = self.generate_assign(name, expression)?;
+        self.deallocate_reg(reg)
+    }
+}
+
+/// Returns the amount of bytes needed for the variables of a function, this is the most variables
+/// which are in scope at the same time.
+fn frame_size(body: &Statement, params: &[(String, DynoType)]) -> DynoResult<usize> {
+    let mut scope = Scope::new();
+    for (name, _) in params {
+        scope.insert(name, ())?;
+    }
+
+    let mut max = scope.all_names().len();
+    count_variables(body, &mut scope, &mut max)?;
+    Ok(8 * max)
+}
+
+fn count_variables(
+    statement: &Statement,
+    scope: &mut Scope<()>,
+    max: &mut usize,
+) -> DynoResult<()> {
+    match statement {
+        Statement::Declaration(name, _) => {
+            scope.insert(name, ())?;
+            *max = (*max).max(scope.all_names().len());
+        }
+        Statement::Block(children) => {
+            scope.push();
+            for child in children {
+                count_variables(child, scope, max)?;
+            }
+            scope.pop()?;
+        }
+        Statement::If(condition, true_statement, false_statement) => {
+            count_expression_variables(condition, scope, max)?;
+            count_variables(true_statement, scope, max)?;
+            if let Some(false_statement) = false_statement {
+                count_variables(false_statement, scope, max)?;
+            }
+        }
+        Statement::While(expression, body) | Statement::DoWhile(body, expression) => {
+            count_expression_variables(expression, scope, max)?;
+            count_variables(body, scope, max)?;
+        }
+        Statement::Assignment(_, expression)
+        | Statement::Return(expression)
+        | Statement::Assert(expression) => count_expression_variables(expression, scope, max)?,
+        Statement::Labeled(_, statement) | Statement::Commented(_, statement) => {
+            count_variables(statement, scope, max)?
+        }
+        Statement::Constant(_, _, _) | Statement::Break(_) | Statement::Continue(_) => {}
+    }
+    Ok(())
+}
+
+/// Counts the variables declared in the block expressions inside of `expression`.
+fn count_expression_variables(
+    expression: &Expression,
+    scope: &mut Scope<()>,
+    max: &mut usize,
+) -> DynoResult<()> {
+    match expression {
+        Expression::Block(statements, expression) => {
+            scope.push();
+            for statement in statements {
+                count_variables(statement, scope, max)?;
+            }
+            count_expression_variables(expression, scope, max)?;
+            scope.pop()?;
+        }
+        Expression::BinaryOperation(_, left, right) => {
+            count_expression_variables(left, scope, max)?;
+            count_expression_variables(right, scope, max)?;
+        }
+        Expression::Widen(expression, _)
+        | Expression::Assign(_, expression)
+        | Expression::Cast(expression, _, _) => count_expression_variables(expression, scope, max)?,
+        Expression::Conditional(condition, true_expression, false_expression) => {
+            count_expression_variables(condition, scope, max)?;
+            count_expression_variables(true_expression, scope, max)?;
+            count_expression_variables(false_expression, scope, max)?;
+        }
+        Expression::Call(_, arguments) => {
+            for (argument, _) in arguments {
+                count_expression_variables(argument, scope, max)?;
+            }
+        }
+        Expression::Literal(_, _) | Expression::Identifier(_) => {}
+    }
+    Ok(())
+}
+
+/// Returns the type of an expression in the scope of the backend, an arithmetic operation has the
+/// type of its right operand which is usually a short expression.
+fn expression_type(
+    expression: &Expression,
+    scope: &Scope<(usize, DynoType)>,
+) -> DynoResult<DynoType> {
+    match expression {
+        Expression::BinaryOperation(op_type, _, right) => {
+            Ok(op_type.result_type(expression_type(right, scope)?))
+        }
+        Expression::Literal(value_type, _)
+        | Expression::Widen(_, value_type)
+        | Expression::Cast(_, value_type, _) => Ok(*value_type),
+        Expression::Identifier(name) | Expression::Assign(name, _) => Ok(scope.find(name)?.1),
+        Expression::Block(statements, expression) => {
+            let mut block_scope = scope.clone();
+            block_scope.push();
+            for statement in statements {
+                if let Statement::Declaration(name, value_type) = statement.without_comments() {
+                    block_scope.insert(name, (0, *value_type))?;
+                }
+            }
+            expression_type(expression, &block_scope)
+        }
+        Expression::Conditional(_, true_expression, _) => expression_type(true_expression, scope),
+        Expression::Call(builtin, arguments) => Ok(builtin.return_type(arguments)),
+    }
+}
+
+/// Returns the amount of registers needed to evaluate the expression, following Sethi-Ullman
+/// numbering.
+fn register_need(expression: &Expression) -> usize {
+    match expression {
+        Expression::BinaryOperation(_, left, right) => {
+            let left = register_need(left);
+            let right = register_need(right);
+            if left == right {
+                left + 1
+            } else {
+                left.max(right)
+            }
+        }
+        Expression::Widen(expression, _)
+        | Expression::Assign(_, expression)
+        | Expression::Cast(expression, _, CastType::Truncate) => register_need(expression),
+        Expression::Cast(expression, _, CastType::Saturate) => register_need(expression).max(2),
+        Expression::Conditional(condition, true_expression, false_expression) => {
+            register_need(condition)
+                .max(register_need(true_expression))
+                .max(register_need(false_expression))
+        }
+        Expression::Block(statements, expression) => {
+            let mut need = register_need(expression);
+            for statement in statements {
+                walk_statement_exprs(statement, &mut |x| need = need.max(register_need(x)));
+            }
+            need
+        }
+        // earlier arguments stay live while the later ones are generated
+        Expression::Call(_, arguments) => arguments
+            .iter()
+            .enumerate()
+            .map(|(i, (x, _))| register_need(x) + i)
+            .max()
+            .unwrap_or(1),
+        Expression::Literal(_, _) | Expression::Identifier(_) => 1,
+    }
+}
+
+fn has_side_effects(expression: &Expression) -> bool {
+    let mut side_effects = false;
+    walk_expr(expression, &mut |x| {
+        if let Expression::Assign(_, _)
+        | Expression::Block(_, _)
+        | Expression::Call(Builtin::ReadInt, _) = x
+        {
+            side_effects = true;
+        }
+    });
+    side_effects
+}
+
+/// Configures how the generated assembly is turned into an executable.
+///
+/// The default uses the compiler set in the `DYNO_CC` environment variable, falling back to `cc`.
+/// When `checked` is set, unsigned subtraction traps on underflow instead of wrapping around, a
+/// subtraction of constants which underflows is an error while compiling.
+/// When `minimize_registers` is set, the operand of a binary operation which needs the most
+/// registers is evaluated first.
+/// Intel syntax is assembled with `nasm` before it is linked by the compiler.
+/// The generated assembly and executables are removed afterwards, unless `keep_artifacts` is set.
+/// The assembly is kept next to the executable with an added `.s` extension.
+#[derive(Debug, Clone)]
+pub struct X86Config {
+    pub compiler: String,
+    pub flags: Vec<String>,
+    pub checked: bool,
+    pub minimize_registers: bool,
+    pub syntax: AsmSyntax,
+    pub keep_artifacts: bool,
+}
+
+impl Default for X86Config {
+    fn default() -> Self {
+        Self {
+            compiler: std::env::var("DYNO_CC").unwrap_or_else(|_| "cc".to_string()),
+            flags: vec![],
+            checked: false,
+            minimize_registers: false,
+            syntax: AsmSyntax::Att,
+            keep_artifacts: false,
+        }
+    }
+}
+
+/// Generates an assembly function with the given name which can be linked into a C program, the
+/// parameters are passed like `uint64_t` arguments and the result is returned in rax.
+///
+/// The labels inside of the function start with its name, so several functions can be written to
+/// the same assembly file.
+pub fn generate_function(
+    name: &str,
+    params: &[(String, DynoType)],
+    body: &Statement,
+    writer: &mut dyn Write,
+) -> DynoResult<()> {
+    let mut backend = X86Backend::new(writer, &X86Config::default());
+    backend.label_prefix = format!("{}_", name);
+    backend.generate_function_header(name, frame_size(body, params)?)?;
+    backend.generate_parameters(params)?;
+    backend.generate_statement(body)?;
+    backend.generate_function_footer()
 }
 
 pub fn compile_and_run(ast: &Statement) -> DynoResult<u64> {
+    compile_and_run_with_config(ast, &X86Config::default())
+}
+
+/// Generates the assembly for the program and turns it into an executable at the given path.
+///
+/// The assembly is written next to the executable with an added `.s` extension.
+pub fn compile_with_config(
+    ast: &Statement,
+    config: &X86Config,
+    executable: &str,
+) -> DynoResult<()> {
+    let assembly_file = format!("{}.s", executable);
+    let object_file = format!("{}.o", executable);
+
+    // a subtraction of constants which underflows would always trap
+    if config.checked {
+        checker::check_underflow(ast)?;
+    }
+
+    let mut backend = X86Backend::new(BufWriter::new(File::create(&assembly_file)?), config);
+    let result = backend
+        .generate_header(ast)
+        .and_then(|_| backend.generate_statement(ast))
+        .and_then(|_| backend.finish())
+        .and_then(|_| assemble(config, &assembly_file, executable));
+
+    if !config.keep_artifacts {
+        remove_artifacts(&[&assembly_file, &object_file]);
+    }
+    result
+}
+
+pub fn compile_and_run_with_config(ast: &Statement, config: &X86Config) -> DynoResult<u64> {
+    compile_and_run_at(ast, config, &temporary_executable()?)
+}
+
+/// Compiles the program to an executable at the given path and runs it, the caller knows where
+/// the artifacts are when they are kept.
+pub fn compile_and_run_at(
+    ast: &Statement,
+    config: &X86Config,
+    executable: &str,
+) -> DynoResult<u64> {
+    compile_with_config(ast, config, executable)?;
+    let output = run_executable(executable);
+
+    if !config.keep_artifacts {
+        remove_artifacts(&[executable]);
+    }
+    output
+}
+
+/// Returns a new path for an executable in `target/x86`, the directory is created if needed.
+pub fn temporary_executable() -> DynoResult<String> {
     std::fs::create_dir_all("target/x86")?;
+    Ok(format!("target/x86/{}.out", timestamp()))
+}
 
-    //TODO: replace this with a hash or something
-    let time = SystemTime::now()
+fn run_executable(executable: &str) -> DynoResult<u64> {
+    // the program prints its result, the exit code is only used to report failures
+    // stdin is passed on for builtins like `read_int`
+    let output = Command::new(executable).stdin(Stdio::inherit()).output()?;
+
+    match output.status.code() {
+        Some(0) => String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .map_err(|_| DynoError::GeneratorError("Failed to read program result".to_string())),
+        Some(code) => Err(DynoError::GeneratorError(format!(
+            "program exited with code {}",
+            code
+        ))),
+        None => Err(DynoError::GeneratorError(format!(
+            "runtime fault: {}",
+            signal_name(output.status.signal())
+        ))),
+    }
+}
+
+/// Removes the files, the ones which were never created are skipped.
+fn remove_artifacts(paths: &[&str]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+//TODO: replace this with a hash or something
+fn timestamp() -> u128 {
+    SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
-        .as_nanos();
-    let assembly_file = format!("target/x86/{}.s", time);
+        .as_nanos()
+}
 
-    let mut backend = X86Backend::new(&assembly_file);
-    backend.generate_header()?;
-    backend.generate_statement(ast)?;
-    backend.finish()?;
+fn signal_name(signal: Option<i32>) -> String {
+    match signal {
+        Some(4) => "SIGILL".to_string(),
+        Some(6) => "SIGABRT".to_string(),
+        Some(7) => "SIGBUS".to_string(),
+        Some(8) => "SIGFPE".to_string(),
+        Some(9) => "SIGKILL".to_string(),
+        Some(11) => "SIGSEGV".to_string(),
+        Some(x) => format!("signal {}", x),
+        None => "unknown signal".to_string(),
+    }
+}
 
-    let executable = format!("target/x86/{}.out", time);
+fn assemble(config: &X86Config, assembly_file: &str, executable: &str) -> DynoResult<()> {
+    let input = match config.syntax {
+        AsmSyntax::Att => assembly_file.to_string(),
+        AsmSyntax::Intel => {
+            let object_file = format!("{}.o", executable);
+            let nasm = Command::new("nasm")
+                .args(["-f", "elf64", assembly_file, "-o", &object_file])
+                .status();
+            check_status("nasm", nasm)?;
+            object_file
+        }
+    };
 
-    let compile_status = Command::new("cc")
-        .arg(&assembly_file)
+    let compile_status = Command::new(&config.compiler)
+        .args(&config.flags)
+        .arg(input)
         .arg("-o")
-        .arg(&executable)
-        .status()?;
+        .arg(executable)
+        .status();
 
-    if compile_status.code().unwrap() != 0 {
-        return Err(DynoError::GeneratorError(
+    check_status(&config.compiler, compile_status)
+}
+
+fn check_status(program: &str, status: std::io::Result<ExitStatus>) -> DynoResult<()> {
+    let status = match status {
+        Ok(status) => status,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            return Err(DynoError::GeneratorError(format!(
+                "{} not found; install a C toolchain",
+                program
+            )))
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    match status.code() {
+        Some(0) => Ok(()),
+        _ => Err(DynoError::GeneratorError(
             "Failed to compile assembly".to_string(),
-        ));
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lex;
+    use crate::parser::parse;
+
+    #[test]
+    fn x86_header_non_executable_stack() -> DynoResult<()> {
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&Statement::Block(vec![]))?;
+        backend.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        assert!(assembly.contains(".section .note.GNU-stack,\"\",@progbits"));
+        Ok(())
+    }
+
+    #[test]
+    fn x86_finish_through_trait_object() -> DynoResult<()> {
+        let ast = parse(lex("let a: u8; a = 3; return a;")?)?;
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+
+        let generic: &mut dyn Backend<Register = Register> = &mut backend;
+        generic.generate_statement(&ast)?;
+        generic.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        assert!(assembly.contains(".Lreturn:"));
+        assert!(assembly.contains("call printf@PLT"));
+        Ok(())
+    }
+
+    #[test]
+    fn x86_function_labels_and_rodata() -> DynoResult<()> {
+        let mut assembly = vec![];
+        generate_function("f", &[], &parse(lex("return 1;")?)?, &mut assembly)?;
+        let assembly = String::from_utf8_lossy(&assembly);
+        assert!(assembly.contains("jmp .Lf_return\n"));
+        assert!(!assembly.contains(".rodata"));
+
+        let mut assembly = vec![];
+        generate_function("g", &[], &parse(lex("return read_int();")?)?, &mut assembly)?;
+        let assembly = String::from_utf8_lossy(&assembly);
+        assert!(assembly.contains(".Lg_read_format:"));
+        assert!(!assembly.contains("format:\n.string \"%lu\\n\""));
+        Ok(())
+    }
+
+    #[test]
+    fn x86_frame_reserved_in_prologue() -> DynoResult<()> {
+        // sibling blocks use the same slot, so two slots are enough
+        let ast = parse(lex(
+            "let a: u8; { let b: u8; b = 1; } { let c: u8; c = 2; } return a;",
+        )?)?;
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+        backend.generate_statement(&ast)?;
+        backend.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        let reservations: Vec<&str> = assembly
+            .lines()
+            .filter(|x| x.starts_with("subq") && x.ends_with(", %rsp"))
+            .collect();
+        assert_eq!(reservations, vec!["subq $16, %rsp"]);
+        assert!(assembly.contains("movb %r8b, -16(%rbp)"));
+        assert!(!assembly.contains("-24(%rbp)"));
+        Ok(())
+    }
+
+    #[test]
+    fn x86_missing_compiler_error() {
+        let config = X86Config {
+            compiler: "dyno_missing_cc".to_string(),
+            ..X86Config::default()
+        };
+        let result = assemble(&config, "target/x86/missing.s", "target/x86/missing.out");
+
+        match result {
+            Err(DynoError::GeneratorError(message)) => {
+                assert_eq!(message, "dyno_missing_cc not found; install a C toolchain")
+            }
+            _ => panic!("expected a generator error, got {:?}", result),
+        }
     }
 
-    //TODO: change this to support 64 bit integer output
-    let status = Command::new(&executable).status()?;
+    #[test]
+    fn x86_unique_labels() -> DynoResult<()> {
+        let ast = parse(lex(r"
+            let a: u32;
+            a = 10;
+            while a > 1 {
+                if a == 5 {
+                    a = a - 1;
+                }
+                if a == 3 {
+                    a = a - 1;
+                }
+                a = a - 1;
+            }
+            return a;")?)?;
+
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+        backend.generate_statement(&ast)?;
+        backend.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        let mut labels: Vec<&str> = assembly.lines().filter(|x| x.ends_with(':')).collect();
+        let label_count = labels.len();
+        labels.sort_unstable();
+        labels.dedup();
+
+        assert_eq!(labels.len(), label_count);
+        Ok(())
+    }
+
+    #[test]
+    fn x86_comparison_zero_extended() -> DynoResult<()> {
+        let ast = parse(lex("return (5 > 3) == (2 < 9);")?)?;
+
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+        backend.generate_statement(&ast)?;
+        backend.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        let lines: Vec<&str> = assembly.lines().collect();
+        let mut set_count = 0;
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_with("set") {
+                set_count += 1;
+                assert!(lines[i + 1].starts_with("movzbq"));
+            }
+        }
+
+        assert_eq!(set_count, 3);
+        Ok(())
+    }
 
-    Ok(status.code().unwrap() as u64)
+    #[test]
+    fn x86_if_else_registers() -> DynoResult<()> {
+        let ast = parse(lex(r"
+            let a: u32;
+            a = 3;
+            if a > 2 {
+                a = a * 2 + 1;
+            } else if a == 1 {
+                a = (a + 4) * (a + 5);
+            } else {
+                a = a - 1;
+            }
+            return a;")?)?;
+
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+        backend.generate_statement(&ast)?;
+        backend.finish()?;
+
+        assert_eq!(backend.regs, [false; 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn x86_intel_syntax() -> DynoResult<()> {
+        let ast = parse(lex("let a: u8; a = 5; return a;")?)?;
+
+        for (syntax, expected) in &[
+            (AsmSyntax::Att, "movb %r8b, -8(%rbp)"),
+            (AsmSyntax::Intel, "mov byte [rbp - 8], r8b"),
+        ] {
+            let config = X86Config {
+                syntax: *syntax,
+                ..X86Config::default()
+            };
+            let mut backend = X86Backend::new(vec![], &config);
+            backend.generate_header(&ast)?;
+            backend.generate_statement(&ast)?;
+            backend.finish()?;
+
+            let assembly = String::from_utf8_lossy(&backend.writer);
+            assert!(assembly.lines().any(|x| x == *expected));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn x86_minimize_registers() -> DynoResult<()> {
+        let ast = parse(lex("return 1 + (2 + (3 + (4 + 5)));")?)?;
+
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+        assert!(backend.generate_statement(&ast).is_err());
+
+        let config = X86Config {
+            minimize_registers: true,
+            ..X86Config::default()
+        };
+        let mut backend = X86Backend::new(vec![], &config);
+        backend.generate_header(&ast)?;
+        backend.generate_statement(&ast)?;
+        backend.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        assert!(!assembly.contains("%r10"));
+        assert!(!assembly.contains("%r11"));
+        Ok(())
+    }
+
+    #[test]
+    fn x86_return_literal() -> DynoResult<()> {
+        let ast = parse(lex("return 42;")?)?;
+
+        let mut backend = X86Backend::new(vec![], &X86Config::default());
+        backend.generate_header(&ast)?;
+        backend.generate_statement(&ast)?;
+        backend.finish()?;
+
+        let assembly = String::from_utf8_lossy(&backend.writer);
+        assert!(assembly.lines().any(|x| x.starts_with("movq $42")));
+        assert!(assembly.lines().any(|x| x == "ret"));
+        Ok(())
+    }
 }
@@ -0,0 +1,271 @@
+use crate::ast::Statement;
+use crate::formatter::{format_expression, format_program};
+
+/// A block of statements which always run one after the other.
+struct BasicBlock {
+    name: String,
+    lines: Vec<String>,
+}
+
+/// The blocks `break` and `continue` jump to for a loop.
+struct LoopTargets {
+    label: Option<String>,
+    condition: usize,
+    exit: usize,
+}
+
+struct CfgBuilder {
+    blocks: Vec<BasicBlock>,
+    edges: Vec<(usize, usize, Option<&'static str>)>,
+    loops: Vec<LoopTargets>,
+}
+
+/// Describes the control flow graph of the program as a Graphviz DOT graph.
+///
+/// The graph is derived from the structure of the ast, every if statement and loop starts new
+/// basic blocks. Statements after a `return`, `break` or `continue` are left out since they can't
+/// be reached.
+pub fn to_dot(statement: &Statement) -> String {
+    let mut builder = CfgBuilder {
+        blocks: vec![],
+        edges: vec![],
+        loops: vec![],
+    };
+
+    let entry = builder.add_block("entry");
+    let exit = builder.add_block("exit");
+    if let Some(end) = builder.build(statement, entry, exit) {
+        builder.edges.push((end, exit, None));
+    }
+
+    let mut result = String::from("digraph cfg {\n    node [shape=box];\n");
+    for (index, block) in builder.blocks.iter().enumerate() {
+        let mut label = format!("{}\\l", escape(&block.name));
+        for line in &block.lines {
+            label.push_str(&escape(line));
+            label.push_str("\\l");
+        }
+        result.push_str(&format!("    b{} [label=\"{}\"];\n", index, label));
+    }
+    for (from, to, label) in &builder.edges {
+        match label {
+            Some(label) => result.push_str(&format!(
+                "    b{} -> b{} [label=\"{}\"];\n",
+                from, to, label
+            )),
+            None => result.push_str(&format!("    b{} -> b{};\n", from, to)),
+        }
+    }
+    result.push_str("}\n");
+
+    result
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl CfgBuilder {
+    fn add_block(&mut self, name: &str) -> usize {
+        self.blocks.push(BasicBlock {
+            name: name.to_string(),
+            lines: vec![],
+        });
+        self.blocks.len() - 1
+    }
+
+    fn add_line(&mut self, block: usize, line: String) {
+        self.blocks[block].lines.push(line);
+    }
+
+    /// Adds the statement to the graph starting in `current`, this returns the block control
+    /// continues in afterwards or `None` when it never continues.
+    fn build(&mut self, statement: &Statement, current: usize, exit: usize) -> Option<usize> {
+        match statement {
+            Statement::Block(statements) => {
+                let mut current = current;
+                for statement in statements {
+                    current = self.build(statement, current, exit)?;
+                }
+                Some(current)
+            }
+            Statement::If(condition, true_statement, false_statement) => {
+                self.add_line(current, format!("if {}", format_expression(condition, 0)));
+
+                let true_block = self.add_block("if true");
+                self.edges.push((current, true_block, Some("true")));
+                let mut ends = vec![self.build(true_statement, true_block, exit)];
+
+                match false_statement {
+                    Some(false_statement) => {
+                        let false_block = self.add_block("if false");
+                        self.edges.push((current, false_block, Some("false")));
+                        ends.push(self.build(false_statement, false_block, exit));
+                    }
+                    None => ends.push(Some(current)),
+                }
+
+                self.join(&ends, false_statement.is_none())
+            }
+            Statement::While(_, _) | Statement::DoWhile(_, _) => {
+                self.build_loop(statement, None, current, exit)
+            }
+            Statement::Labeled(label, statement) => {
+                self.build_loop(statement, Some(label), current, exit)
+            }
+            Statement::Break(label) | Statement::Continue(label) => {
+                let targets = self
+                    .loops
+                    .iter()
+                    .rev()
+                    .find(|x| label.is_none() || x.label == *label)?;
+                let target = match statement {
+                    Statement::Break(_) => targets.exit,
+                    _ => targets.condition,
+                };
+
+                self.add_line(current, format_program(statement).trim_end().to_string());
+                self.edges.push((current, target, None));
+                None
+            }
+            Statement::Return(_) => {
+                self.add_line(current, format_program(statement).trim_end().to_string());
+                self.edges.push((current, exit, None));
+                None
+            }
+            Statement::Commented(_, statement) => self.build(statement, current, exit),
+            Statement::Declaration(_, _)
+            | Statement::Constant(_, _, _)
+            | Statement::Assignment(_, _)
+            | Statement::Assert(_) => {
+                for line in format_program(statement).lines() {
+                    self.add_line(current, line.to_string());
+                }
+                Some(current)
+            }
+        }
+    }
+
+    /// Continues in a new block after the branches which don't diverge. When `from_condition` is
+    /// set, the last branch is the block with the condition which jumps over the if statement.
+    fn join(&mut self, ends: &[Option<usize>], from_condition: bool) -> Option<usize> {
+        if ends.iter().all(|x| x.is_none()) {
+            return None;
+        }
+
+        let next = self.add_block("continue");
+        for (index, end) in ends.iter().enumerate() {
+            if let Some(end) = end {
+                let label = if from_condition && index == ends.len() - 1 {
+                    Some("false")
+                } else {
+                    None
+                };
+                self.edges.push((*end, next, label));
+            }
+        }
+
+        Some(next)
+    }
+
+    fn build_loop(
+        &mut self,
+        statement: &Statement,
+        label: Option<&String>,
+        current: usize,
+        exit: usize,
+    ) -> Option<usize> {
+        let (condition, body, condition_first) = match statement {
+            Statement::While(condition, body) => (condition, body, true),
+            Statement::DoWhile(body, condition) => (condition, body, false),
+            _ => return self.build(statement, current, exit),
+        };
+
+        let header = self.add_block("while header");
+        self.add_line(header, format!("while {}", format_expression(condition, 0)));
+        let body_block = self.add_block("while body");
+        let next = self.add_block("continue");
+
+        // a do while loop runs the body before checking the condition
+        let start = if condition_first { header } else { body_block };
+        self.edges.push((current, start, None));
+        self.edges.push((header, body_block, Some("true")));
+        self.edges.push((header, next, Some("false")));
+
+        self.loops.push(LoopTargets {
+            label: label.cloned(),
+            condition: header,
+            exit: next,
+        });
+        let end = self.build(body, body_block, exit);
+        self.loops.pop();
+
+        if let Some(end) = end {
+            self.edges.push((end, header, None));
+        }
+
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DynoResult;
+    use crate::lexer::lex;
+    use crate::parser::parse;
+
+    #[test]
+    fn cfg_if_else() -> DynoResult<()> {
+        let ast = parse(lex(
+            "let a: u8; if a == 1 { a = 2; } else { a = 3; } return a;",
+        )?)?;
+        let dot = to_dot(&ast);
+
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("b0 [label=\"entry\\llet a: u8;\\lif a == 1\\l\"];"));
+        assert!(dot.contains("b2 [label=\"if true\\la = 2;\\l\"];"));
+        assert!(dot.contains("b3 [label=\"if false\\la = 3;\\l\"];"));
+        assert!(dot.contains("b4 [label=\"continue\\lreturn a;\\l\"];"));
+        for edge in &[
+            "b0 -> b2 [label=\"true\"];",
+            "b0 -> b3 [label=\"false\"];",
+            "b2 -> b4;",
+            "b3 -> b4;",
+            "b4 -> b1;",
+        ] {
+            assert!(dot.contains(edge), "missing {} in {}", edge, dot);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cfg_if_without_else() -> DynoResult<()> {
+        let dot = to_dot(&parse(lex("let a: u8; if a == 1 { return 1; } a = 2;")?)?);
+
+        assert!(dot.contains("b2 -> b1;"));
+        assert!(dot.contains("b0 -> b3 [label=\"false\"];"));
+        assert!(dot.contains("b3 [label=\"continue\\la = 2;\\l\"];"));
+        Ok(())
+    }
+
+    #[test]
+    fn cfg_while_loop() -> DynoResult<()> {
+        let dot = to_dot(&parse(lex(
+            "let a: u8; while a < 3 { if a == 1 { break; } a = a + 1; }",
+        )?)?);
+
+        assert!(dot.contains("b2 [label=\"while header\\lwhile a < 3\\l\"];"));
+        for edge in &[
+            "b0 -> b2;",
+            "b2 -> b3 [label=\"true\"];",
+            "b2 -> b4 [label=\"false\"];",
+            "b5 -> b4;",
+            "b6 -> b2;",
+            "b4 -> b1;",
+        ] {
+            assert!(dot.contains(edge), "missing {} in {}", edge, dot);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use crate::ast::{walk, walk_statement_exprs, BinaryOperationType, Expression, Statement};
+use crate::error::{DynoError, DynoResult};
+use crate::formatter::{format_expression, format_program};
+use crate::types::DynoType;
+
+/// Returns the statements which can never run because a statement in front of them in the same
+/// block always returns or jumps out of the loop. Only the first unreachable statement of a block
+/// is returned.
+pub fn find_unreachable(statement: &Statement) -> Vec<&Statement> {
+    let mut unreachable = vec![];
+
+    find_in_blocks(statement, &mut unreachable);
+    // walk doesn't enter block expressions, so their statements are searched separately
+    walk_statement_exprs(statement, &mut |expression| {
+        if let Expression::Block(statements, _) = expression {
+            unreachable.extend(first_unreachable(statements));
+            for statement in statements {
+                find_in_blocks(statement, &mut unreachable);
+            }
+        }
+    });
+
+    unreachable
+}
+
+fn find_in_blocks<'a>(statement: &'a Statement, unreachable: &mut Vec<&'a Statement>) {
+    walk(statement, &mut |statement| {
+        if let Statement::Block(statements) = statement {
+            unreachable.extend(first_unreachable(statements));
+        }
+    });
+}
+
+/// Results in an error for the first unreachable statement, this is the strict version of
+/// `find_unreachable`.
+pub fn check_reachable(statement: &Statement) -> DynoResult<()> {
+    match find_unreachable(statement).first() {
+        Some(statement) => Err(DynoError::VisitError(format!(
+            "unreachable statement `{}`",
+            format_program(statement).trim_end()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Results in an error for the first subtraction of constants which underflows, checked code would
+/// always trap on it.
+pub fn check_underflow(statement: &Statement) -> DynoResult<()> {
+    let mut underflow = None;
+    walk_statement_exprs(statement, &mut |expression| {
+        if let Expression::BinaryOperation(BinaryOperationType::Subtract, left, right) = expression
+        {
+            if let (Some((_, left)), Some((_, right))) =
+                (constant_value(left), constant_value(right))
+            {
+                if left < right && underflow.is_none() {
+                    underflow = Some(expression);
+                }
+            }
+        }
+    });
+
+    match underflow {
+        Some(expression) => Err(DynoError::VisitError(format!(
+            "subtraction `{}` underflows",
+            format_expression(expression, 0)
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Returns the type and value of an expression which only consists of literals, the value wraps
+/// around like it does at runtime.
+fn constant_value(expression: &Expression) -> Option<(DynoType, u64)> {
+    use BinaryOperationType::*;
+
+    match expression {
+        Expression::Literal(value_type, value) => Some((*value_type, value.to_bits())),
+        Expression::Widen(expression, value_type) => {
+            Some((*value_type, constant_value(expression)?.1))
+        }
+        Expression::BinaryOperation(op_type, left, right) => {
+            let (value_type, left) = constant_value(left)?;
+            let (_, right) = constant_value(right)?;
+            let value = match op_type {
+                Add => left.wrapping_add(right),
+                Subtract => left.wrapping_sub(right),
+                Multiply => left.wrapping_mul(right),
+                Divide => left.checked_div(right)?,
+                Equal => (left == right) as u64,
+                NotEqual => (left != right) as u64,
+                LessThan => (left < right) as u64,
+                LessThanEqual => (left <= right) as u64,
+                GreaterThan => (left > right) as u64,
+                GreaterThanEqual => (left >= right) as u64,
+            };
+            let value_type = op_type.result_type(value_type);
+            Some((value_type, value_type.wrap(value)))
+        }
+        _ => None,
+    }
+}
+
+fn first_unreachable(statements: &[Statement]) -> Option<&Statement> {
+    let position = statements.iter().position(diverges)?;
+    statements.get(position + 1)
+}
+
+/// Returns whether the statement never continues with the statement after it.
+fn diverges(statement: &Statement) -> bool {
+    match statement {
+        Statement::Return(_) | Statement::Break(_) | Statement::Continue(_) => true,
+        Statement::Block(statements) => statements.iter().any(diverges),
+        // without an else branch the condition can be false
+        Statement::If(_, true_statement, Some(false_statement)) => {
+            diverges(true_statement) && diverges(false_statement)
+        }
+        Statement::Commented(_, statement) => diverges(statement),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lex;
+    use crate::parser::parse;
+
+    fn unreachable(input: &str) -> DynoResult<Vec<String>> {
+        let ast = parse(lex(input)?)?;
+        Ok(find_unreachable(&ast)
+            .into_iter()
+            .map(|x| format_program(x).trim_end().to_string())
+            .collect())
+    }
+
+    #[test]
+    fn unreachable_after_return() -> DynoResult<()> {
+        assert_eq!(unreachable("return 1; return 2;")?, vec!["return 2;"]);
+        assert_eq!(
+            check_reachable(&parse(lex("return 1; return 2;")?)?),
+            Err(DynoError::VisitError(
+                "unreachable statement `return 2;`".to_string()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_if_branches() -> DynoResult<()> {
+        let input = "let a: u8; if a == 1 { return 1; } return 2;";
+        assert!(unreachable(input)?.is_empty());
+
+        let input = "let a: u8; if a == 1 { return 1; } else { return 3; } a = 2;";
+        assert_eq!(unreachable(input)?, vec!["a = 2;"]);
+        Ok(())
+    }
+
+    #[test]
+    fn underflow_of_constants() -> DynoResult<()> {
+        let check = |input| check_underflow(&parse(lex(input)?)?);
+        assert_eq!(
+            check("let x: u8; x = 0 - 1;"),
+            Err(DynoError::VisitError(
+                "subtraction `0 - 1` underflows".to_string()
+            ))
+        );
+        // the folded value of the left operand is too small as well
+        assert!(check("let x: u8; x = (250 + 10) - 5;").is_err());
+        assert!(check("return 5 - (2 + 2);").is_ok());
+        assert!(check("let a: u8; return a - 1;").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_nested_blocks() -> DynoResult<()> {
+        let input = "let a: u8; while true { break; a = 1; } a = { return 1; a = 2; 3 };";
+        assert_eq!(unreachable(input)?, vec!["a = 1;", "a = 2;"]);
+
+        let input = "let a: u8; a = { while true { continue; a = 1; } 3 };";
+        assert_eq!(unreachable(input)?, vec!["a = 1;"]);
+        assert!(check_reachable(&parse(lex("let a: u8; a = 1; return a;")?)?).is_ok());
+        Ok(())
+    }
+}
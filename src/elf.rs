@@ -18,20 +18,46 @@ pub enum ElfType {
 /// Struct used to generate an ELF file.
 ///
 /// The `program_header_table`, `section_header_table` and `code` field will written to the output
-/// file.
+/// file. When `comment` is set, it is written after the section names in a `.comment` section which
+/// is added after the other sections.
 pub struct ElfFileInfo {
     pub program_header_table: Vec<ElfProgramHeaderEntry>,
     pub section_header_table: Vec<ElfSectionHeaderEntry>,
     pub code: Vec<u8>,
+    pub comment: Option<String>,
+}
+
+const PROGRAM_TABLE_ENTRY_SIZE: u16 = 56;
+
+/// The index of the `.shstrtab` section which holds the section names.
+const SECTION_NAMES_INDEX: u16 = 2;
+
+/// The comment which identifies the compiler that produced a file.
+pub fn version_comment() -> String {
+    format!("dyno {}", env!("CARGO_PKG_VERSION"))
 }
 
 impl ElfFileInfo {
+    /// Returns the names of all sections, including the comment section.
+    fn section_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .section_header_table
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect();
+        if self.comment.is_some() {
+            names.push(".comment");
+        }
+
+        names
+    }
+
     /// Returns a byte array containing the names of all sections
     pub fn get_names(&self) -> DynoResult<Vec<u8>> {
         let mut writer = std::io::BufWriter::new(vec![]);
 
-        for section in &self.section_header_table {
-            write(&mut writer, section.name.as_bytes())?;
+        for name in self.section_names() {
+            write(&mut writer, name.as_bytes())?;
             write(&mut writer, &[0x00])?;
         }
 
@@ -40,20 +66,62 @@ impl ElfFileInfo {
 
     /// Calculates the offset of a section name in the name byte array
     pub fn get_name_offset(&self, section_index: usize) -> u32 {
-        if self.section_header_table[section_index].name.is_empty() {
+        let names = self.section_names();
+        if names[section_index].is_empty() {
             return 0;
         }
 
         let mut result: u32 = 1;
-        for i in 0..section_index {
-            if !self.section_header_table[i].name.is_empty() {
+        for name in &names[..section_index] {
+            if !name.is_empty() {
                 // Add the size of the name plus one for a null byte
-                result += self.section_header_table[i].name.len() as u32 + 1;
+                result += name.len() as u32 + 1;
             }
         }
 
         result
     }
+
+    /// Returns the contents of the comment section, a null terminated string.
+    pub fn get_comment(&self) -> Vec<u8> {
+        match &self.comment {
+            Some(comment) => {
+                let mut result = comment.as_bytes().to_vec();
+                result.push(0x00);
+                result
+            }
+            None => vec![],
+        }
+    }
+
+    /// Returns the offset of the comment in the file, it comes right after the section names.
+    pub fn get_comment_offset(&self) -> DynoResult<u64> {
+        Ok(0x40
+            + PROGRAM_TABLE_ENTRY_SIZE as u64 * self.program_header_table.len() as u64
+            + 8
+            + self.code.len() as u64
+            + self.get_names()?.len() as u64)
+    }
+
+    /// Returns the header of the comment section, if there is a comment.
+    fn comment_section(&self) -> DynoResult<Option<ElfSectionHeaderEntry>> {
+        if self.comment.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(ElfSectionHeaderEntry {
+            name: ".comment".to_string(),
+            section_type: ElfSectionType::ShtProgBits,
+            flags: ELF_SECTION_FLAG_MERGE | ELF_SECTION_FLAG_STRINGS,
+            address: 0x00,
+            offset: self.get_comment_offset()?,
+            size: self.get_comment().len() as u64,
+            link: 0x00,
+            info: 0x00,
+            address_align: 0x01,
+            entry_size: 0x01,
+        }))
+    }
 }
 
 /// An enum representing the type of an ELF program header entry.
@@ -157,7 +225,7 @@ pub const NULL_SECTION: ElfSectionHeaderEntry = ElfSectionHeaderEntry {
 /// This function tries to write a byte array using a Write trait. If the write fails it returns a
 /// DynoError::ElfWriteError. If it succeeds, it returns an empty Ok value.
 fn write(writer: &mut dyn Write, data: &[u8]) -> DynoResult<()> {
-    match writer.write(data) {
+    match writer.write_all(data) {
         Ok(_) => Ok(()),
         Err(_) => Err(DynoError::ElfWriteError()),
     }
@@ -186,10 +254,6 @@ where
     // abi and pad
     write(writer, &[0x00; 8])?;
 
-    const PROGRAM_TABLE_ENTRY_SIZE: u16 = 56;
-    let program_header_size =
-        PROGRAM_TABLE_ENTRY_SIZE * file_info.program_header_table.len() as u16;
-
     const SECTION_TABLE_ENTRY_SIZE: u16 = 64;
 
     // elf type
@@ -207,15 +271,10 @@ where
     // program header offset
     write(writer, &(0x40_u64).to_le_bytes())?;
 
-    // section table offset
+    // section table offset, this comes after everything else
     write(
         writer,
-        &(0x40
-            + program_header_size as u64
-            + file_info.code.len() as u64
-            + file_info.get_names()?.len() as u64
-            + 8)
-        .to_le_bytes(),
+        &(file_info.get_comment_offset()? + file_info.get_comment().len() as u64).to_le_bytes(),
     )?;
 
     // flags
@@ -239,11 +298,11 @@ where
     // section header entry num
     write(
         writer,
-        &(file_info.section_header_table.len() as u16).to_le_bytes(),
+        &(file_info.section_names().len() as u16).to_le_bytes(),
     )?;
 
     // section name header table entry
-    write(writer, &(0x02_u16).to_le_bytes())?;
+    write(writer, &SECTION_NAMES_INDEX.to_le_bytes())?;
 
     Ok(())
 }
@@ -287,7 +346,10 @@ fn write_elf_section_header<T>(writer: &mut T, elf_file: &ElfFileInfo) -> DynoRe
 where
     T: Write,
 {
-    for (index, section) in elf_file.section_header_table.iter().enumerate() {
+    let comment_section = elf_file.comment_section()?;
+    let sections = elf_file.section_header_table.iter().chain(&comment_section);
+
+    for (index, section) in sections.enumerate() {
         // calculate the starting index in the .shstrtab section
         let name_index: u32 = elf_file.get_name_offset(index);
         write(writer, &name_index.to_le_bytes())?;
@@ -304,8 +366,13 @@ where
         // the offset of this section
         write(writer, &section.offset.to_le_bytes())?;
 
-        // the size of the section
-        write(writer, &section.size.to_le_bytes())?;
+        // the size of the section, the section names include the name of the comment section
+        let size = if index == SECTION_NAMES_INDEX as usize {
+            elf_file.get_names()?.len() as u64
+        } else {
+            section.size
+        };
+        write(writer, &size.to_le_bytes())?;
 
         // an optional link to another section
         write(writer, &section.link.to_le_bytes())?;
@@ -343,6 +410,9 @@ where
     // writes the names of all the sections
     write(writer, &elf_file.get_names()?)?;
 
+    // writes the optional comment
+    write(writer, &elf_file.get_comment())?;
+
     // writes the section table header
     write_elf_section_header(writer, elf_file)?;
 
@@ -353,11 +423,8 @@ where
 mod tests {
     use super::*;
 
-    #[test]
-    fn elf_write_full_file() {
-        let mut writer = std::io::BufWriter::new(vec![]);
-
-        let elf_file = ElfFileInfo {
+    fn test_file(comment: Option<String>) -> ElfFileInfo {
+        ElfFileInfo {
             program_header_table: vec![ElfProgramHeaderEntry {
                 segment_type: ElfProgramHeaderEntryType::PtLoad,
                 flags: ELF_PROGRAM_FLAG_READ | ELF_PROGRAM_FLAG_EXECUTE,
@@ -398,8 +465,54 @@ mod tests {
             code: vec![
                 0xB8, 0x01, 0x00, 0x00, 0x00, 0xBB, 0x2A, 0x00, 0x00, 0x00, 0xCD, 0x80,
             ],
-        };
+            comment,
+        }
+    }
 
-        write_elf_file(&mut writer, &elf_file).unwrap();
+    fn read_u64(bytes: &[u8], offset: usize) -> usize {
+        let mut value = [0; 8];
+        value.copy_from_slice(&bytes[offset..offset + 8]);
+        u64::from_le_bytes(value) as usize
+    }
+
+    #[test]
+    fn elf_write_full_file() {
+        let mut writer = std::io::BufWriter::new(vec![]);
+
+        write_elf_file(&mut writer, &test_file(None)).unwrap();
+    }
+
+    #[test]
+    fn elf_write_comment_section() {
+        let mut bytes = vec![];
+        write_elf_file(&mut bytes, &test_file(Some(version_comment()))).unwrap();
+
+        let section_table = read_u64(&bytes, 0x28);
+        let section_count = u16::from_le_bytes([bytes[0x3C], bytes[0x3D]]) as usize;
+        assert_eq!(section_count, 4);
+        assert_eq!(bytes.len(), section_table + 64 * section_count);
+
+        // the comment section comes last and points at the null terminated comment
+        let header = section_table + 64 * 3;
+        let offset = read_u64(&bytes, header + 0x18);
+        let size = read_u64(&bytes, header + 0x20);
+        assert_eq!(
+            &bytes[offset..offset + size],
+            format!("dyno {}\0", env!("CARGO_PKG_VERSION")).as_bytes()
+        );
+
+        let name = u32::from_le_bytes([
+            bytes[header],
+            bytes[header + 1],
+            bytes[header + 2],
+            bytes[header + 3],
+        ]) as usize;
+        // the section names are found through the header at index `e_shstrndx`
+        let names_index = u16::from_le_bytes([bytes[0x3E], bytes[0x3F]]) as usize;
+        let names_header = section_table + 64 * names_index;
+        let names = read_u64(&bytes, names_header + 0x18);
+        let names_size = read_u64(&bytes, names_header + 0x20);
+        assert!(name + 9 <= names_size);
+        assert_eq!(&bytes[names + name..names + name + 9], b".comment\0");
     }
 }
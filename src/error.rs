@@ -1,6 +1,8 @@
+use crate::lexer::Lexer;
 use crate::token::TokenType;
 use crate::types::DynoType;
 use std::fmt;
+use std::ops::Range;
 
 #[derive(Debug)]
 pub enum DynoError {
@@ -8,8 +10,16 @@ pub enum DynoError {
     TokenStreamOutOfBounds(),
     IntegerParseError(String),
     UnexpectedTokenError(TokenType, Vec<TokenType>),
-    IncompatibleTypeError(DynoType, DynoType),
+    /// Two types which can't be used together, with the source span of the expression if known.
+    IncompatibleTypeError(DynoType, DynoType, Option<Range<usize>>),
     IdentifierError(String),
+    DuplicateDefinition(String),
+    IncludeError(String),
+    DepthLimitError(usize),
+    ControlFlowError(String),
+    ConstantError(String),
+    /// A comment which can't be attached to a statement, so formatting would lose it.
+    CommentError(String),
     ElfWriteError(),
     X86WriteError(),
     GeneratorError(String),
@@ -31,6 +41,80 @@ impl From<std::io::Error> for DynoError {
     }
 }
 
+/// Errors compare by their contents, except for the expected tokens of an unexpected token error
+/// which compare as a set so their order doesn't matter. IO errors only compare their kind.
+impl PartialEq for DynoError {
+    fn eq(&self, other: &Self) -> bool {
+        use DynoError::*;
+
+        match (self, other) {
+            (LexerError(left), LexerError(right))
+            | (IntegerParseError(left), IntegerParseError(right))
+            | (IdentifierError(left), IdentifierError(right))
+            | (DuplicateDefinition(left), DuplicateDefinition(right))
+            | (IncludeError(left), IncludeError(right))
+            | (ControlFlowError(left), ControlFlowError(right))
+            | (ConstantError(left), ConstantError(right))
+            | (CommentError(left), CommentError(right))
+            | (GeneratorError(left), GeneratorError(right))
+            | (VisitError(left), VisitError(right)) => left == right,
+            (
+                UnexpectedTokenError(left, left_expected),
+                UnexpectedTokenError(right, right_expected),
+            ) => {
+                left == right
+                    && left_expected.iter().all(|x| right_expected.contains(x))
+                    && right_expected.iter().all(|x| left_expected.contains(x))
+            }
+            (
+                IncompatibleTypeError(left_a, left_b, left_span),
+                IncompatibleTypeError(right_a, right_b, right_span),
+            ) => left_a == right_a && left_b == right_b && left_span == right_span,
+            (DepthLimitError(left), DepthLimitError(right)) => left == right,
+            (IOError(left), IOError(right)) => left.kind() == right.kind(),
+            (TokenStreamOutOfBounds(), TokenStreamOutOfBounds())
+            | (ElfWriteError(), ElfWriteError())
+            | (X86WriteError(), X86WriteError())
+            | (NoneError(), NoneError())
+            | (IntoInnerError(), IntoInnerError()) => true,
+            _ => false,
+        }
+    }
+}
+
+impl DynoError {
+    /// Sets the source span of a type error which doesn't have one yet, so the innermost
+    /// expression which has a span is reported.
+    pub fn with_span(self, span: Range<usize>) -> Self {
+        match self {
+            DynoError::IncompatibleTypeError(left, right, None) => {
+                DynoError::IncompatibleTypeError(left, right, Some(span))
+            }
+            error => error,
+        }
+    }
+
+    /// Returns the source span the error refers to, if known.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            DynoError::IncompatibleTypeError(_, _, span) => span.clone(),
+            _ => None,
+        }
+    }
+
+    /// Formats the error together with the line and column it refers to in the source, both
+    /// counting from 1.
+    pub fn describe(&self, source: &str) -> String {
+        match self.span() {
+            Some(span) => {
+                let position = Lexer::new(source).position(span.start.min(source.len()));
+                format!("{} at {}:{}", self, position.line, position.column)
+            }
+            None => self.to_string(),
+        }
+    }
+}
+
 pub type DynoResult<T> = Result<T, DynoError>;
 
 impl fmt::Display for DynoError {
@@ -48,10 +132,18 @@ impl fmt::Display for DynoError {
                     received, expected
                 )
             }
-            IncompatibleTypeError(left, right) => {
+            IncompatibleTypeError(left, right, _) => {
                 write!(f, "Incompatible types {:?} and {:?}", left, right)
             }
             IdentifierError(message) => write!(f, "Identifier error: {}", message),
+            DuplicateDefinition(name) => {
+                write!(f, "Identifier already defined in scope: {}", name)
+            }
+            IncludeError(message) => write!(f, "Include error: {}", message),
+            DepthLimitError(depth) => write!(f, "Nesting deeper than {} levels", depth),
+            ControlFlowError(message) => write!(f, "Control flow error: {}", message),
+            ConstantError(message) => write!(f, "Constant error: {}", message),
+            CommentError(message) => write!(f, "Comment error: {}", message),
             ElfWriteError() => write!(f, "Error while writing ELF file"),
             X86WriteError() => write!(f, "Error while writing x86 assembly"),
             GeneratorError(message) => write!(f, "Code generator error: {}", message),
@@ -62,3 +154,30 @@ impl fmt::Display for DynoError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_expected_tokens_unordered() {
+        use TokenType::*;
+
+        assert_eq!(
+            DynoError::UnexpectedTokenError(Colon, vec![Plus, Minus, Slash]),
+            DynoError::UnexpectedTokenError(Colon, vec![Slash, Plus, Minus])
+        );
+        assert_ne!(
+            DynoError::UnexpectedTokenError(Colon, vec![Plus, Minus]),
+            DynoError::UnexpectedTokenError(Colon, vec![Plus, Slash])
+        );
+        assert_ne!(
+            DynoError::UnexpectedTokenError(Colon, vec![Plus]),
+            DynoError::UnexpectedTokenError(SemiColon, vec![Plus])
+        );
+        assert_ne!(
+            DynoError::LexerError("a".to_string()),
+            DynoError::IdentifierError("a".to_string())
+        );
+    }
+}
@@ -0,0 +1,574 @@
+use crate::ast::{walk, walk_statement_exprs, CastType, Expression, Statement};
+use crate::error::*;
+use crate::lexer::Lexer;
+use crate::parser::parse;
+use crate::token::{Token, TokenType};
+use crate::types::DynoValue;
+
+const INDENT: &str = "    ";
+
+/// Lexes and parses the input and writes it back out in the canonical format.
+///
+/// Comments are kept on the statement they are in front of or behind, a comment anywhere else
+/// results in a `CommentError`.
+pub fn format_source(input: &str) -> DynoResult<String> {
+    let tokens = mark_trailing_comments(
+        Lexer::new(input)
+            .keep_whitespace(true)
+            .keep_comments(true)
+            .get_tokens()?,
+    );
+
+    let mut comments: Vec<String> = tokens
+        .iter()
+        .filter(|x| {
+            matches!(
+                x.token_type,
+                TokenType::Comment | TokenType::TrailingComment
+            )
+        })
+        .map(|x| x.value.trim_end().to_string())
+        .collect();
+
+    let ast = parse(tokens)?;
+    for comment in attached_comments(&ast) {
+        if let Some(index) = comments.iter().position(|x| x == comment) {
+            comments.remove(index);
+        }
+    }
+    if let Some(comment) = comments.first() {
+        return Err(DynoError::CommentError(format!(
+            "`{}` isn't in front of or behind a statement",
+            comment
+        )));
+    }
+
+    Ok(format_program(&ast))
+}
+
+/// Returns the comments attached to the statements, including those inside block expressions.
+fn attached_comments(ast: &Statement) -> Vec<&String> {
+    let mut result = vec![];
+    collect_comments(ast, &mut result);
+    walk_statement_exprs(ast, &mut |expression| {
+        if let Expression::Block(statements, _) = expression {
+            for statement in statements {
+                collect_comments(statement, &mut result);
+            }
+        }
+    });
+    result
+}
+
+fn collect_comments<'a>(statement: &'a Statement, result: &mut Vec<&'a String>) {
+    walk(statement, &mut |statement| {
+        if let Statement::Commented(comments, _) = statement {
+            result.extend(&comments.leading);
+            result.extend(&comments.trailing);
+            result.extend(&comments.after);
+        }
+    });
+}
+
+/// Removes the whitespace and turns comments behind other tokens on the same line into
+/// `TrailingComment` tokens.
+fn mark_trailing_comments(tokens: Vec<Token>) -> Vec<Token> {
+    let mut result = vec![];
+    let mut line_start = true;
+
+    for mut token in tokens {
+        match token.token_type {
+            TokenType::Whitespace => {
+                line_start |= token.contains_newline();
+                continue;
+            }
+            TokenType::Comment if !line_start => token.token_type = TokenType::TrailingComment,
+            TokenType::Comment => {}
+            _ => line_start = false,
+        }
+        result.push(token);
+    }
+
+    result
+}
+
+/// Formats a parsed program, the statements of a top level block are not wrapped in braces.
+pub fn format_program(statement: &Statement) -> String {
+    let mut result = String::new();
+
+    match statement {
+        Statement::Block(children) => {
+            for child in children {
+                format_statement(&mut result, child, 0);
+            }
+        }
+        _ => format_statement(&mut result, statement, 0),
+    }
+
+    result
+}
+
+fn write_line(result: &mut String, line: &str, indent: usize) {
+    for _ in 0..indent {
+        result.push_str(INDENT);
+    }
+    result.push_str(line);
+    result.push('\n');
+}
+
+/// Writes the body of an `if` or `while`, this is always delimited by braces.
+fn format_body(result: &mut String, header: &str, body: &Statement, indent: usize) {
+    write_line(result, &format!("{} {{", header), indent);
+
+    match body {
+        Statement::Block(children) => {
+            for child in children {
+                format_statement(result, child, indent + 1);
+            }
+        }
+        _ => format_statement(result, body, indent + 1),
+    }
+}
+
+fn format_if(
+    result: &mut String,
+    header: &str,
+    body: &Statement,
+    false_body: Option<&Statement>,
+    indent: usize,
+) {
+    format_body(result, header, body, indent);
+
+    match false_body {
+        Some(Statement::If(condition, body, false_body)) => {
+            let header = format!("}} else if {}", format_expression(condition, indent));
+            format_if(result, &header, body, false_body.as_deref(), indent);
+        }
+        Some(false_body) => {
+            format_body(result, "} else", false_body, indent);
+            write_line(result, "}", indent);
+        }
+        None => write_line(result, "}", indent),
+    }
+}
+
+fn format_statement(result: &mut String, statement: &Statement, indent: usize) {
+    match statement {
+        Statement::Declaration(name, value_type) => {
+            write_line(result, &format!("let {}: {};", name, value_type), indent)
+        }
+        Statement::Constant(name, value_type, value) => write_line(
+            result,
+            &format!(
+                "const {}: {} = {};",
+                name,
+                value_type,
+                format_expression(&Expression::Literal(*value_type, *value), indent)
+            ),
+            indent,
+        ),
+        Statement::Assignment(name, expression) => write_line(
+            result,
+            &format!("{} = {};", name, format_expression(expression, indent)),
+            indent,
+        ),
+        Statement::If(condition, body, false_body) => {
+            let header = format!("if {}", format_expression(condition, indent));
+            format_if(result, &header, body, false_body.as_deref(), indent);
+        }
+        Statement::While(_, _) | Statement::DoWhile(_, _) => {
+            format_loop(result, "", statement, indent)
+        }
+        Statement::Labeled(label, statement) => {
+            format_loop(result, &format!("{}: ", label), statement, indent)
+        }
+        Statement::Break(label) => write_line(result, &loop_control("break", label), indent),
+        Statement::Continue(label) => write_line(result, &loop_control("continue", label), indent),
+        Statement::Return(expression) => write_line(
+            result,
+            &format!("return {};", format_expression(expression, indent)),
+            indent,
+        ),
+        Statement::Assert(condition) => write_line(
+            result,
+            &format!("assert({});", format_expression(condition, indent)),
+            indent,
+        ),
+        Statement::Commented(comments, statement) => {
+            for comment in &comments.leading {
+                write_line(result, comment, indent);
+            }
+            format_statement(result, statement, indent);
+            if let Some(comment) = &comments.trailing {
+                result.pop();
+                result.push(' ');
+                result.push_str(comment);
+                result.push('\n');
+            }
+            for comment in &comments.after {
+                write_line(result, comment, indent);
+            }
+        }
+        Statement::Block(children) => {
+            write_line(result, "{", indent);
+            for child in children {
+                format_statement(result, child, indent + 1);
+            }
+            write_line(result, "}", indent);
+        }
+    }
+}
+
+/// Writes a loop with the prefix in front of it, this is used for labels.
+fn format_loop(result: &mut String, prefix: &str, statement: &Statement, indent: usize) {
+    match statement {
+        Statement::While(condition, body) => {
+            let header = format!("{}while {}", prefix, format_expression(condition, indent));
+            format_body(result, &header, body, indent);
+            write_line(result, "}", indent);
+        }
+        Statement::DoWhile(body, condition) => {
+            format_body(result, &format!("{}do", prefix), body, indent);
+            let footer = format!("}} while {};", format_expression(condition, indent));
+            write_line(result, &footer, indent);
+        }
+        _ => format_statement(result, statement, indent),
+    }
+}
+
+fn loop_control(keyword: &str, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("{} {};", keyword, label),
+        None => format!("{};", keyword),
+    }
+}
+
+/// Skips over the widen nodes inserted by the parser, they have no source representation.
+fn strip_widen(expression: &Expression) -> &Expression {
+    match expression {
+        Expression::Widen(inner, _) => strip_widen(inner),
+        _ => expression,
+    }
+}
+
+fn format_operand(
+    expression: &Expression,
+    precedence: u8,
+    is_right: bool,
+    indent: usize,
+) -> String {
+    let formatted = format_expression(expression, indent);
+
+    match strip_widen(expression) {
+        Expression::BinaryOperation(op_type, _, _) => {
+            // binary operations are left associative so the right operand needs parentheses when
+            // the precedence is equal
+            let child_precedence = op_type.get_precedence();
+            if child_precedence < precedence || (is_right && child_precedence == precedence) {
+                format!("({})", formatted)
+            } else {
+                formatted
+            }
+        }
+        Expression::Assign(_, _) | Expression::Conditional(_, _, _) => format!("({})", formatted),
+        _ => formatted,
+    }
+}
+
+/// Formats an expression, block expressions inside of it are indented by `indent` levels.
+pub fn format_expression(expression: &Expression, indent: usize) -> String {
+    match expression {
+        Expression::BinaryOperation(op_type, left, right) => {
+            let precedence = op_type.get_precedence();
+            format!(
+                "{} {} {}",
+                format_operand(left, precedence, false, indent),
+                op_type,
+                format_operand(right, precedence, true, indent)
+            )
+        }
+        Expression::Literal(_, DynoValue::UInt(value)) => value.to_string(),
+        Expression::Literal(_, DynoValue::Bool(value)) => value.to_string(),
+        Expression::Widen(inner, _) => format_expression(inner, indent),
+        Expression::Identifier(name) => name.clone(),
+        Expression::Call(builtin, arguments) if builtin.is_method() => {
+            let receiver = &arguments[0].0;
+            let receiver = match strip_widen(receiver) {
+                Expression::BinaryOperation(_, _, _)
+                | Expression::Assign(_, _)
+                | Expression::Conditional(_, _, _)
+                | Expression::Cast(_, _, _) => {
+                    format!("({})", format_expression(receiver, indent))
+                }
+                _ => format_expression(receiver, indent),
+            };
+            format!(
+                "{}.{}({})",
+                receiver,
+                builtin.name(),
+                format_expression(&arguments[1].0, indent)
+            )
+        }
+        Expression::Call(builtin, arguments) => {
+            let arguments: Vec<String> = arguments
+                .iter()
+                .map(|(x, _)| format_expression(x, indent))
+                .collect();
+            format!("{}({})", builtin.name(), arguments.join(", "))
+        }
+        Expression::Assign(name, expression) => {
+            format!("{} = {}", name, format_expression(expression, indent))
+        }
+        Expression::Cast(inner, value_type, cast_type) => {
+            let inner = match strip_widen(inner) {
+                Expression::BinaryOperation(_, _, _)
+                | Expression::Assign(_, _)
+                | Expression::Conditional(_, _, _) => {
+                    format!("({})", format_expression(inner, indent))
+                }
+                _ => format_expression(inner, indent),
+            };
+            let operator = match cast_type {
+                CastType::Truncate => "as",
+                CastType::Saturate => "as!",
+            };
+            format!("{} {} {}", inner, operator, value_type)
+        }
+        Expression::Conditional(condition, true_expression, false_expression) => {
+            // the condition is parsed up to the question mark so only a nested conditional needs
+            // parentheses
+            let condition = match strip_widen(condition) {
+                Expression::Conditional(_, _, _) => {
+                    format!("({})", format_expression(condition, indent))
+                }
+                _ => format_expression(condition, indent),
+            };
+            format!(
+                "{} ? {} : {}",
+                condition,
+                format_expression(true_expression, indent),
+                format_expression(false_expression, indent)
+            )
+        }
+        Expression::Block(statements, expression) => {
+            let mut result = "{\n".to_string();
+            for statement in statements {
+                format_statement(&mut result, statement, indent + 1);
+            }
+            write_line(
+                &mut result,
+                &format_expression(expression, indent + 1),
+                indent + 1,
+            );
+            for _ in 0..indent {
+                result.push_str(INDENT);
+            }
+            result.push('}');
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_ugly_input() -> DynoResult<()> {
+        let input =
+            "let a:u32;a=12-2*4;if a>1{a=(a-1)*2;}  while a>1{{let b:u8;b=1;}a=a-(1-0);}return a;";
+        let expected = r"let a: u32;
+a = 12 - 2 * 4;
+if a > 1 {
+    a = (a - 1) * 2;
+}
+while a > 1 {
+    {
+        let b: u8;
+        b = 1;
+    }
+    a = a - (1 - 0);
+}
+return a;
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_if_else() -> DynoResult<()> {
+        let input = "let a:u8;if a==1{a=2;}else if a==2{a=3;a=4;}else{a=5;}";
+        let expected = r"let a: u8;
+if a == 1 {
+    a = 2;
+} else if a == 2 {
+    a = 3;
+    a = 4;
+} else {
+    a = 5;
+}
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_assignment_expression() -> DynoResult<()> {
+        let formatted = format_source("let a:u8;let b:u8;a=b=5;a=(b=2)+1;")?;
+        assert_eq!(
+            formatted,
+            "let a: u8;\nlet b: u8;\na = b = 5;\na = (b = 2) + 1;\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_bool_literals() -> DynoResult<()> {
+        let formatted = format_source("let a:bool;a=true==false;")?;
+        assert_eq!(formatted, "let a: bool;\na = true == false;\n");
+        Ok(())
+    }
+
+    #[test]
+    fn format_block_expression() -> DynoResult<()> {
+        let input = "let a: u32; a = {let t: u32; t = 4*(2+1); t};return a;";
+        let expected = r"let a: u32;
+a = {
+    let t: u32;
+    t = 4 * (2 + 1);
+    t
+};
+return a;
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_conditional() -> DynoResult<()> {
+        let input = "let a:u8;a=1+(a<5?1:a<9?2:3);a=(true?a==1:false)?a:2;";
+        let expected = "let a: u8;\na = 1 + (a < 5 ? 1 : a < 9 ? 2 : 3);\na = (true ? a == 1 : false) ? a : 2;\n";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_comments() -> DynoResult<()> {
+        let input = "// the counter\nlet a:u8;a=0;\nwhile a<3 {\n  // step\n  a=a+1;}\nreturn a;";
+        let expected = r"// the counter
+let a: u8;
+a = 0;
+while a < 3 {
+    // step
+    a = a + 1;
+}
+return a;
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_trailing_comments() -> DynoResult<()> {
+        let input = "let a:u8; // counter\nwhile a<1 {a=1; // trailing\n} // loop\nwhile a<2 {\na=2;\n// end\n}\nreturn a; // done\n// eof";
+        let expected = r"let a: u8; // counter
+while a < 1 {
+    a = 1; // trailing
+} // loop
+while a < 2 {
+    a = 2;
+    // end
+}
+return a; // done
+// eof
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        assert_eq!(format_source("return 4; // done")?, "return 4; // done\n");
+        Ok(())
+    }
+
+    #[test]
+    fn format_unattached_comments() {
+        let error = |comment: &str| {
+            Err(DynoError::CommentError(format!(
+                "`{}` isn't in front of or behind a statement",
+                comment
+            )))
+        };
+
+        assert_eq!(format_source("return 1 + // one\n 2;"), error("// one"));
+        assert_eq!(format_source("return 1 +\n// one\n2;"), error("// one"));
+        assert_eq!(
+            format_source("while false {\n// empty\n}"),
+            error("// empty")
+        );
+        assert_eq!(
+            format_source("while false { // empty\n}"),
+            error("// empty")
+        );
+    }
+
+    #[test]
+    fn format_casts() -> DynoResult<()> {
+        let input = "let a:u16;a=(a+1)as!u8*2 as u8+a as u8;";
+        let expected = "let a: u16;\na = (a + 1) as! u8 * 2 as u8 + a as u8;\n";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_labeled_loops() -> DynoResult<()> {
+        let input = "outer:while true{while true{break outer;}continue;}";
+        let expected = r"outer: while true {
+    while true {
+        break outer;
+    }
+    continue;
+}
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn format_do_while() -> DynoResult<()> {
+        let input = "let a:u8;do{a=a+1;}while a<3;outer:do{break outer;}while true;";
+        let expected = r"let a: u8;
+do {
+    a = a + 1;
+} while a < 3;
+outer: do {
+    break outer;
+} while true;
+";
+
+        let formatted = format_source(input)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted)?, formatted);
+        Ok(())
+    }
+}
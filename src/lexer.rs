@@ -1,11 +1,22 @@
 use crate::error::*;
 use crate::token::{Token, TokenType};
 use regex::Regex;
+use std::ops::Range;
+
+/// A position in the source, lines and columns both start at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
 
 pub struct Lexer<'a> {
     rules: Vec<(Regex, TokenType)>,
     input: &'a str,
     pointer: usize,
+    keep_whitespace: bool,
+    keep_comments: bool,
+    tab_width: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -14,15 +25,26 @@ impl<'a> Lexer<'a> {
 
         let rules = vec![
             (r"[ \t\n\f]+", Whitespace),
+            (r"//[^\n]*", Comment),
+            (r"/\*(?s:.)*?\*/", Comment),
             (r"let", Let),
+            (r"const", Const),
             (r"while", While),
+            (r"do", Do),
             (r"return", Return),
             (r"if", If),
+            (r"else", Else),
+            (r"assert", Assert),
+            (r"break", Break),
+            (r"continue", Continue),
+            (r"as", As),
+            (r"as!", AsSaturating),
             (r"u8", UInt8),
             (r"u16", UInt16),
             (r"u32", UInt32),
             (r"u64", UInt64),
             (r"bool", Bool),
+            (r"true|false", BoolLiteral),
             (r"[a-zA-Z][_a-zA-Z]*", Identifier),
             (r"[0-9]+", IntegerLiteral),
             (r"\+", Plus),
@@ -37,6 +59,8 @@ impl<'a> Lexer<'a> {
             (r">", GreaterThan),
             (r"=", Equals),
             (r":", Colon),
+            (r"\?", Question),
+            (r"\.", Dot),
             (r";", SemiColon),
             (r"\(", LeftParen),
             (r"\)", RightParen),
@@ -53,44 +77,101 @@ impl<'a> Lexer<'a> {
             rules,
             input,
             pointer: 0,
+            keep_whitespace: false,
+            keep_comments: false,
+            tab_width: 4,
         }
     }
 
-    pub fn get_tokens(&mut self) -> DynoResult<Vec<Token>> {
-        let mut result = vec![];
+    /// Keep whitespace tokens in the output instead of filtering them out.
+    pub fn keep_whitespace(mut self, keep_whitespace: bool) -> Self {
+        self.keep_whitespace = keep_whitespace;
+        self
+    }
 
-        loop {
-            if self.pointer >= self.input.len() {
-                break;
-            }
+    /// Keep comment tokens in the output instead of filtering them out.
+    pub fn keep_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
 
-            let mut matches = vec![];
+    /// Sets the amount of columns a tab advances to, this is used when reporting positions.
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
 
-            for rule in &self.rules {
-                let (regex, token_type) = rule;
+    /// Returns the line and column of a byte offset in the input, tabs advance the column to the
+    /// next multiple of the tab width.
+    pub fn position(&self, offset: usize) -> Position {
+        let mut position = Position { line: 1, column: 1 };
 
-                if let Some(x) = regex.find(&self.input[self.pointer..]) {
-                    if x.start() == 0 {
-                        matches.push(Token::new_with_span(*token_type, x.as_str(), x.range()));
-                    }
+        for character in self.input[..offset].chars() {
+            match character {
+                '\n' => {
+                    position.line += 1;
+                    position.column = 1;
+                }
+                '\t' if self.tab_width > 0 => {
+                    position.column += self.tab_width - (position.column - 1) % self.tab_width
                 }
+                _ => position.column += 1,
             }
+        }
+
+        position
+    }
+
+    /// Matches the longest token at the current position, earlier rules win on equal lengths.
+    fn next_token(&self) -> DynoResult<Token> {
+        if self.pointer >= self.input.len() {
+            return Err(DynoError::TokenStreamOutOfBounds());
+        }
+
+        let mut matches = vec![];
 
-            if matches.is_empty() {
-                return Err(DynoError::LexerError("Unable to lex".to_string()));
+        for rule in &self.rules {
+            let (regex, token_type) = rule;
+
+            if let Some(x) = regex.find(&self.input[self.pointer..]) {
+                if x.start() == 0 {
+                    let span = self.pointer..self.pointer + x.end();
+                    matches.push(Token::new_with_span(*token_type, x.as_str(), span));
+                }
             }
+        }
 
-            matches.sort_by(|a, b| (b.span.end - b.span.start).cmp(&(a.span.end - a.span.start)));
+        if matches.is_empty() {
+            // the pointer is always on a char boundary since every match ends on one
+            let character = self.input[self.pointer..]
+                .chars()
+                .next()
+                .unwrap_or_default();
+            return Err(DynoError::LexerError(format!(
+                "Unable to lex: unexpected {:?} at offset {}",
+                character, self.pointer
+            )));
+        }
 
-            let best_match = matches.remove(0);
-            self.pointer += best_match.span.end;
+        matches.sort_by_key(|x| std::cmp::Reverse(x.span.end - x.span.start));
 
-            result.push(best_match);
+        Ok(matches.remove(0))
+    }
+
+    pub fn get_tokens(&mut self) -> DynoResult<Vec<Token>> {
+        let mut result = vec![];
+
+        while self.pointer < self.input.len() {
+            let token = self.next_token()?;
+            self.pointer = token.span.end;
+
+            result.push(token);
         }
 
         Ok(result
             .into_iter()
-            .filter(|x| x.token_type != TokenType::Whitespace)
+            .filter(|x| self.keep_whitespace || x.token_type != TokenType::Whitespace)
+            .filter(|x| self.keep_comments || x.token_type != TokenType::Comment)
             .collect())
     }
 }
@@ -99,6 +180,31 @@ pub fn lex(input: &str) -> DynoResult<Vec<Token>> {
     Lexer::new(input).get_tokens()
 }
 
+/// Lexes the single token at the start of the input and returns it together with its length in
+/// bytes. Whitespace and comments are returned as tokens as well.
+///
+/// Empty input results in `TokenStreamOutOfBounds`, input which doesn't start with a token results
+/// in a `LexerError`.
+pub fn lex_one(input: &str) -> DynoResult<(Token, usize)> {
+    let token = Lexer::new(input).next_token()?;
+    let length = token.span.end;
+    Ok((token, length))
+}
+
+/// Returns the type and source span of every token, optionally including whitespace.
+///
+/// This is meant for tooling like syntax highlighters which don't need the parser.
+pub fn tokens_with_spans(
+    input: &str,
+    keep_whitespace: bool,
+) -> DynoResult<Vec<(TokenType, Range<usize>)>> {
+    let tokens = Lexer::new(input)
+        .keep_whitespace(keep_whitespace)
+        .get_tokens()?;
+
+    Ok(tokens.into_iter().map(|x| (x.token_type, x.span)).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::TokenType::*;
@@ -129,11 +235,12 @@ mod tests {
 
     #[test]
     fn lexer_keywords() {
-        let tokens = get_tokens("let return if");
+        let tokens = get_tokens("let return if else");
 
         assert_eq!(tokens[0].token_type, Let);
         assert_eq!(tokens[1].token_type, Return);
         assert_eq!(tokens[2].token_type, If);
+        assert_eq!(tokens[3].token_type, Else);
     }
 
     #[test]
@@ -170,6 +277,15 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn lexer_bool_literal() {
+        let tokens = get_tokens("true false trueish");
+
+        assert_eq!(tokens[0], Token::new(BoolLiteral, "true"));
+        assert_eq!(tokens[1], Token::new(BoolLiteral, "false"));
+        assert_eq!(tokens[2], Token::new(Identifier, "trueish"));
+    }
+
     #[test]
     fn lexer_identifier() {
         let tokens = get_tokens("test test_with_underscore");
@@ -179,16 +295,131 @@ mod tests {
     }
 
     #[test]
-    fn lexer_identifier_error() {
-        let tokens = lex("_identifier");
+    fn lexer_spans() -> DynoResult<()> {
+        let tokens = lex("let ab: u8;")?;
+
+        assert_eq!(tokens[0].span, 0..3);
+        assert_eq!(tokens[1].span, 4..6);
+        assert_eq!(tokens[2].span, 6..7);
+        assert_eq!(tokens[3].span, 8..10);
+        assert_eq!(tokens[4].span, 10..11);
+        Ok(())
+    }
 
-        assert!(tokens.is_err());
+    #[test]
+    fn lexer_tokens_with_spans() -> DynoResult<()> {
+        let tokens = tokens_with_spans("return  1;", true)?;
+        assert_eq!(
+            tokens,
+            vec![
+                (Return, 0..6),
+                (Whitespace, 6..8),
+                (IntegerLiteral, 8..9),
+                (SemiColon, 9..10)
+            ]
+        );
+
+        let tokens = tokens_with_spans("return  1;", false)?;
+        assert_eq!(
+            tokens,
+            vec![(Return, 0..6), (IntegerLiteral, 8..9), (SemiColon, 9..10)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lexer_comments() -> DynoResult<()> {
+        let input = "// first\nreturn 1 / 2; // second";
+
+        let tokens = lex(input)?;
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[2].token_type, Slash);
+
+        let tokens = Lexer::new(input).keep_comments(true).get_tokens()?;
+        assert_eq!(tokens[0], Token::new(Comment, "// first"));
+        assert_eq!(tokens[6], Token::new(Comment, "// second"));
+        Ok(())
+    }
+
+    #[test]
+    fn lexer_block_comments() -> DynoResult<()> {
+        let input = "return 1 + /* x */ 2 /* a\nb */ / 2; /**/";
+
+        let tokens = lex(input)?;
+        assert_eq!(tokens.len(), 7);
+        assert_eq!(tokens[2].token_type, Plus);
+        assert_eq!(tokens[4].token_type, Slash);
+
+        let tokens = Lexer::new(input).keep_comments(true).get_tokens()?;
+        assert_eq!(tokens[3], Token::new(Comment, "/* x */"));
+        assert_eq!(tokens[5], Token::new(Comment, "/* a\nb */"));
+        assert_eq!(tokens[9], Token::new(Comment, "/**/"));
+        Ok(())
+    }
+
+    #[test]
+    fn lexer_positions() -> DynoResult<()> {
+        let input = "let a: u8;\n\ta = 1;\n  \treturn a;";
+        let mut lexer = Lexer::new(input).keep_whitespace(true);
+        let tokens = lexer.get_tokens()?;
+
+        let newlines: Vec<bool> = tokens
+            .iter()
+            .filter(|x| x.token_type == Whitespace)
+            .map(|x| x.contains_newline())
+            .collect();
+        assert_eq!(
+            newlines,
+            vec![false, false, true, false, false, true, false]
+        );
+        let a = tokens.iter().find(|x| x.span.start == 12).unwrap();
+        assert_eq!(a.value, "a");
+        assert_eq!(
+            lexer.position(a.span.start),
+            Position { line: 2, column: 5 }
+        );
+
+        let lexer = Lexer::new(input).tab_width(8);
+        assert_eq!(lexer.position(12), Position { line: 2, column: 9 });
+        // the tab after two spaces only advances to the next tab stop
+        assert_eq!(lexer.position(22), Position { line: 3, column: 9 });
+        Ok(())
+    }
+
+    #[test]
+    fn lexer_identifier_error() {
+        match lex("_identifier") {
+            Err(DynoError::LexerError(message)) => {
+                assert_eq!(message, "Unable to lex: unexpected '_' at offset 0")
+            }
+            x => panic!("expected a lexer error, got {:?}", x),
+        }
     }
 
     #[test]
     fn lexer_test_error() {
-        let tokens = lex("return &;");
+        match lex("return &;") {
+            Err(DynoError::LexerError(message)) => {
+                assert_eq!(message, "Unable to lex: unexpected '&' at offset 7")
+            }
+            x => panic!("expected a lexer error, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn lexer_lex_one() -> DynoResult<()> {
+        let buffer = "let a: u8; a = 255;";
+
+        let (token, length) = lex_one(&buffer[15..])?;
+        assert_eq!(token, Token::new(IntegerLiteral, "255"));
+        assert_eq!(length, 3);
 
-        assert!(tokens.is_err());
+        // whitespace is a token of its own, and `letter` isn't lexed as `let`
+        assert_eq!(lex_one(" \tletter")?.0, Token::new(Whitespace, " \t"));
+        assert_eq!(lex_one("letter")?, (Token::new(Identifier, "letter"), 6));
+
+        assert_eq!(lex_one(""), Err(DynoError::TokenStreamOutOfBounds()));
+        assert!(matches!(lex_one("&a"), Err(DynoError::LexerError(_))));
+        Ok(())
     }
 }
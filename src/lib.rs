@@ -1,9 +1,65 @@
 pub mod ast;
 pub mod backend;
+pub mod cfg;
+pub mod checker;
 pub mod elf;
 pub mod error;
+pub mod formatter;
 pub mod lexer;
+pub mod optimizer;
 pub mod parser;
 pub mod scope;
 pub mod token;
 pub mod types;
+
+use error::{DynoError, DynoResult};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use types::DynoType;
+
+/// Interprets the source and returns the result together with its static type.
+///
+/// Programs without a return statement result in `(0, DynoType::Void())`.
+pub fn eval(src: &str) -> DynoResult<(u64, DynoType)> {
+    let (ast, return_type) = parser::parse_with_return_type(lexer::lex(src)?)?;
+    Ok((backend::interp::run(&ast)?, return_type))
+}
+
+/// Reads the file and textually replaces every `include "path";` line with the contents of the
+/// included file. Paths are relative to the including file, an include cycle results in an error.
+pub fn expand_includes<P: AsRef<Path>>(path: P) -> DynoResult<String> {
+    let include = Regex::new(r#"^\s*include\s+"([^"]+)"\s*;\s*$"#).unwrap();
+    expand_file(path.as_ref(), &include, &mut vec![])
+}
+
+fn expand_file(path: &Path, include: &Regex, active: &mut Vec<PathBuf>) -> DynoResult<String> {
+    let path = path.canonicalize()?;
+    if active.contains(&path) {
+        return Err(DynoError::IncludeError(format!(
+            "{} includes itself",
+            path.display()
+        )));
+    }
+
+    let input = std::fs::read_to_string(&path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    active.push(path.clone());
+
+    let mut result = String::new();
+    for line in input.lines() {
+        match include.captures(line) {
+            Some(captures) => result.push_str(&expand_file(
+                &directory.join(&captures[1]),
+                include,
+                active,
+            )?),
+            None => {
+                result.push_str(line);
+                result.push('\n');
+            }
+        }
+    }
+
+    active.pop();
+    Ok(result)
+}
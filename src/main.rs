@@ -1,62 +1,227 @@
+use dyno::backend::x86_backend::X86Config;
 use dyno::*;
 use std::env;
 use std::io::{stdin, stdout, Write};
+use std::path::Path;
 
-fn read_input() -> String {
+/// Reads a line from stdin, this returns `None` once the input is closed.
+fn read_input() -> Option<String> {
     let mut input = String::new();
 
     print!("> ");
 
     let _ = stdout().flush();
 
-    stdin()
+    let read = stdin()
         .read_line(&mut input)
         .expect("Did not enter a correct input");
 
-    input
+    match read {
+        0 => None,
+        _ => Some(input),
+    }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn format_file(path: &str) {
+    let input = match std::fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+
+    match formatter::format_source(&input) {
+        Ok(formatted) => print!("{}", formatted),
+        Err(error) => {
+            eprintln!("Failed to format {}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Lexes and parses the input, the requested dumps are written to `dump`.
+fn parse_source(
+    input: &str,
+    args: &[String],
+    dump: &mut dyn Write,
+) -> Result<ast::Statement, String> {
+    // Lexing
+
+    let tokens =
+        lexer::lex(input).map_err(|error| format!("Failed to tokenize input: {}", error))?;
+
+    if args.contains(&"--lex".to_string()) {
+        let _ = writeln!(dump, "\nTokens:");
+        let tokens: Vec<String> = tokens.iter().map(|x| x.to_string()).collect();
+        let _ = writeln!(dump, "{}", tokens.join(" "));
+    }
+
+    // Parsing
+
+    let ast = parser::parse(tokens)
+        .map_err(|error| format!("Failed to create ast: {}", error.describe(input)))?;
+
+    if args.contains(&"--ast".to_string()) {
+        let _ = writeln!(dump, "\nAst:");
+        let _ = writeln!(dump, "{:#?}", ast);
+    }
+
+    // unreachable code is only an error with `--strict`
+    if args.contains(&"--strict".to_string()) {
+        checker::check_reachable(&ast)
+            .map_err(|error| format!("Failed to check ast: {}", error))?;
+    } else {
+        for statement in checker::find_unreachable(&ast) {
+            eprintln!(
+                "Warning: unreachable statement `{}`",
+                formatter::format_program(statement).trim_end()
+            );
+        }
+    }
+
+    Ok(ast)
+}
+
+fn x86_config(args: &[String]) -> X86Config {
+    X86Config {
+        keep_artifacts: args.contains(&"--x86-keep".to_string()),
+        ..X86Config::default()
+    }
+}
+
+/// Prints the paths of the artifacts which were kept with `--x86-keep`, the executable is only an
+/// artifact when it was compiled to be run.
+fn report_kept(config: &X86Config, executable: &str, ran: bool) {
+    if !config.keep_artifacts {
+        return;
+    }
+
+    let assembly = format!("{}.s", executable);
+    if Path::new(&assembly).exists() {
+        eprintln!("Kept assembly at {}", assembly);
+    }
+    if ran && Path::new(executable).exists() {
+        eprintln!("Kept executable at {}", executable);
+    }
+}
 
-    loop {
-        let input = read_input();
+/// Lexes, parses and runs the input, the requested dumps are written to `dump`.
+fn run_source(input: &str, args: &[String], dump: &mut dyn Write) -> Result<u64, String> {
+    let ast = parse_source(input, args, dump)?;
 
-        // Lexing
+    let config = x86_config(args);
+    let executable = backend::x86_backend::temporary_executable()
+        .map_err(|error| format!("Failed to compile and run ast: {}", error))?;
+    let result = backend::x86_backend::compile_and_run_at(&ast, &config, &executable);
+    report_kept(&config, &executable, true);
+    result.map_err(|error| format!("Failed to compile and run ast: {}", error))
+}
 
-        let tokens = lexer::lex(&input);
-        if tokens.is_err() {
-            eprintln!("Failed to tokenize input: {}", tokens.err().unwrap());
-            continue;
+fn read_file(path: &str) -> String {
+    match expand_includes(path) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", path, error);
+            std::process::exit(1);
         }
-        let tokens = tokens.unwrap();
+    }
+}
+
+/// Compiles a source file to an executable without running it.
+fn compile_file(path: &str, output: &str, args: &[String]) {
+    let input = read_file(path);
 
-        if args.contains(&"--lex".to_string()) {
-            println!("\nTokens:");
-            println!("{:#?}", tokens);
+    let config = x86_config(args);
+    let result = parse_source(&input, args, &mut std::io::stderr()).and_then(|ast| {
+        let result = backend::x86_backend::compile_with_config(&ast, &config, output);
+        report_kept(&config, output, false);
+        result.map_err(|error| format!("Failed to compile ast: {}", error))
+    });
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+/// Writes the control flow graph of a source file to stdout as a DOT graph.
+fn emit_cfg(path: &str, args: &[String]) {
+    let input = read_file(path);
+
+    match parse_source(&input, args, &mut std::io::stderr()) {
+        Ok(ast) => print!("{}", cfg::to_dot(&ast)),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
         }
+    }
+}
 
-        // Parsing
+/// Runs a source file, the dumps go to stderr so stdout only contains the result.
+fn run_file(path: &str, args: &[String]) {
+    let input = read_file(path);
 
-        let ast = parser::parse(tokens);
-        if ast.is_err() {
-            eprintln!("Failed to create ast: {}", ast.err().unwrap());
-            continue;
+    match run_source(&input, args, &mut std::io::stderr()) {
+        Ok(result) => println!("{}", result),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
         }
-        let ast = ast.unwrap();
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() > 2 && args[1] == "fmt" {
+        format_file(&args[2]);
+        return;
+    }
 
-        if args.contains(&"--ast".to_string()) {
-            println!("\nAst:");
-            println!("{:#?}", ast);
+    // `-o` is followed by the output path, every other argument without a dash is the source file
+    let output = args
+        .iter()
+        .position(|x| x == "-o")
+        .and_then(|i| args.get(i + 1));
+    let path = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(i, x)| !x.starts_with('-') && args[i - 1] != "-o")
+        .map(|(_, x)| x);
+
+    if args.contains(&"--compile".to_string()) {
+        match (path, output) {
+            (Some(path), Some(output)) => compile_file(path, output, &args),
+            _ => {
+                eprintln!("Usage: dyno --compile <file> -o <output>");
+                std::process::exit(1);
+            }
         }
+        return;
+    }
 
-        let result = backend::x86_backend::compile_and_run(&ast);
-        if result.is_err() {
-            eprintln!("Failed to compile and run ast: {}", result.err().unwrap());
-            continue;
+    if args.contains(&"--emit-cfg".to_string()) {
+        match path {
+            Some(path) => emit_cfg(path, &args),
+            None => {
+                eprintln!("Usage: dyno --emit-cfg <file>");
+                std::process::exit(1);
+            }
         }
-        let result = result.unwrap();
+        return;
+    }
 
-        println!("=> {}", result);
+    if let Some(path) = path {
+        run_file(path, &args);
+        return;
+    }
+
+    while let Some(input) = read_input() {
+        match run_source(&input, &args, &mut stdout()) {
+            Ok(result) => println!("=> {}", result),
+            Err(error) => eprintln!("{}", error),
+        }
     }
 }
@@ -0,0 +1,336 @@
+use crate::ast::{BinaryOperationType, CastType, Expression, Statement};
+use crate::scope::Scope;
+use crate::types::{DynoType, DynoValue};
+
+/// Folds the constants in the statement and removes the branches which are never taken.
+///
+/// An if statement with a constant condition is replaced by the branch which is taken, a
+/// `while false` loop is removed entirely. A taken branch keeps its own block, so the variables
+/// declared in it stay out of the enclosing scope.
+pub fn optimize(statement: Statement) -> Statement {
+    eliminate_dead_branches(fold_constants(statement)).unwrap_or(Statement::Block(vec![]))
+}
+
+/// Returns the statement without its dead branches, or `None` when nothing of it would run.
+fn eliminate_dead_branches(statement: Statement) -> Option<Statement> {
+    let or_empty = |x: Option<Statement>| Box::new(x.unwrap_or(Statement::Block(vec![])));
+
+    match statement {
+        Statement::If(Expression::Literal(_, DynoValue::Bool(true)), true_statement, _) => {
+            eliminate_dead_branches(*true_statement)
+        }
+        Statement::If(Expression::Literal(_, DynoValue::Bool(false)), _, false_statement) => {
+            false_statement.and_then(|x| eliminate_dead_branches(*x))
+        }
+        Statement::If(condition, true_statement, false_statement) => Some(Statement::If(
+            condition,
+            or_empty(eliminate_dead_branches(*true_statement)),
+            false_statement
+                .and_then(|x| eliminate_dead_branches(*x))
+                .map(Box::new),
+        )),
+        Statement::While(Expression::Literal(_, DynoValue::Bool(false)), _) => None,
+        Statement::While(condition, body) => Some(Statement::While(
+            condition,
+            or_empty(eliminate_dead_branches(*body)),
+        )),
+        Statement::DoWhile(body, condition) => Some(Statement::DoWhile(
+            or_empty(eliminate_dead_branches(*body)),
+            condition,
+        )),
+        Statement::Block(statements) => {
+            let mut statements: Vec<Statement> = statements
+                .into_iter()
+                .filter_map(eliminate_dead_branches)
+                .collect();
+
+            // a block only affects the scope when it contains declarations
+            match statements.as_slice() {
+                [] => None,
+                [statement]
+                    if !matches!(
+                        statement.without_comments(),
+                        Statement::Declaration(_, _) | Statement::Constant(_, _, _)
+                    ) =>
+                {
+                    Some(statements.remove(0))
+                }
+                _ => Some(Statement::Block(statements)),
+            }
+        }
+        Statement::Commented(comments, statement) => {
+            eliminate_dead_branches(*statement).map(|x| Statement::Commented(comments, Box::new(x)))
+        }
+        Statement::Labeled(label, statement) => {
+            eliminate_dead_branches(*statement).map(|x| Statement::Labeled(label, Box::new(x)))
+        }
+        statement => Some(statement),
+    }
+}
+
+/// Replaces every operation on constant values in the statement by its result.
+pub fn fold_constants(statement: Statement) -> Statement {
+    match statement {
+        Statement::Assignment(name, expression) => {
+            Statement::Assignment(name, fold_expression(expression))
+        }
+        Statement::If(condition, true_statement, false_statement) => Statement::If(
+            fold_expression(condition),
+            Box::new(fold_constants(*true_statement)),
+            false_statement.map(|x| Box::new(fold_constants(*x))),
+        ),
+        Statement::While(condition, body) => {
+            Statement::While(fold_expression(condition), Box::new(fold_constants(*body)))
+        }
+        Statement::DoWhile(body, condition) => {
+            Statement::DoWhile(Box::new(fold_constants(*body)), fold_expression(condition))
+        }
+        Statement::Return(expression) => Statement::Return(fold_expression(expression)),
+        Statement::Assert(condition) => Statement::Assert(fold_expression(condition)),
+        Statement::Block(statements) => {
+            Statement::Block(statements.into_iter().map(fold_constants).collect())
+        }
+        Statement::Commented(comments, statement) => {
+            Statement::Commented(comments, Box::new(fold_constants(*statement)))
+        }
+        Statement::Labeled(label, statement) => {
+            Statement::Labeled(label, Box::new(fold_constants(*statement)))
+        }
+        Statement::Declaration(_, _)
+        | Statement::Constant(_, _, _)
+        | Statement::Break(_)
+        | Statement::Continue(_) => statement,
+    }
+}
+
+/// Folds the constant parts of an expression.
+///
+/// The folded values wrap around at the size of the type of the operation, a widened constant
+/// takes on the widened type. Divisions by zero are left alone so they still fault at runtime.
+pub fn fold_expression(expression: Expression) -> Expression {
+    match expression {
+        Expression::BinaryOperation(op_type, left, right) => {
+            let left = fold_expression(*left);
+            let right = fold_expression(*right);
+            let expression = Expression::BinaryOperation(op_type, Box::new(left), Box::new(right));
+
+            match fold_binop(&expression) {
+                Some(folded) => folded,
+                None => expression,
+            }
+        }
+        Expression::Widen(expression, value_type) => match fold_expression(*expression) {
+            Expression::Literal(_, DynoValue::UInt(x)) if value_type.is_int() => {
+                Expression::Literal(value_type, DynoValue::UInt(value_type.wrap(x)))
+            }
+            expression => Expression::Widen(Box::new(expression), value_type),
+        },
+        Expression::Block(statements, expression) => Expression::Block(
+            statements.into_iter().map(fold_constants).collect(),
+            Box::new(fold_expression(*expression)),
+        ),
+        Expression::Assign(name, expression) => {
+            Expression::Assign(name, Box::new(fold_expression(*expression)))
+        }
+        Expression::Cast(expression, value_type, cast_type) => match fold_expression(*expression) {
+            Expression::Literal(_, DynoValue::UInt(x)) => {
+                let value = match cast_type {
+                    CastType::Truncate => value_type.wrap(x),
+                    CastType::Saturate => x.min(value_type.wrap(u64::MAX)),
+                };
+                Expression::Literal(value_type, DynoValue::UInt(value))
+            }
+            expression => Expression::Cast(Box::new(expression), value_type, cast_type),
+        },
+        Expression::Conditional(condition, true_expression, false_expression) => {
+            match fold_expression(*condition) {
+                Expression::Literal(_, DynoValue::Bool(true)) => fold_expression(*true_expression),
+                Expression::Literal(_, DynoValue::Bool(false)) => {
+                    fold_expression(*false_expression)
+                }
+                condition => Expression::Conditional(
+                    Box::new(condition),
+                    Box::new(fold_expression(*true_expression)),
+                    Box::new(fold_expression(*false_expression)),
+                ),
+            }
+        }
+        Expression::Call(builtin, arguments) => Expression::Call(
+            builtin,
+            arguments
+                .into_iter()
+                .map(|(x, value_type)| (fold_expression(x), value_type))
+                .collect(),
+        ),
+        Expression::Literal(_, _) | Expression::Identifier(_) => expression,
+    }
+}
+
+fn fold_binop(expression: &Expression) -> Option<Expression> {
+    use BinaryOperationType::*;
+
+    let (op_type, left, right) = match expression {
+        Expression::BinaryOperation(op_type, left, right) => {
+            match (left.as_ref(), right.as_ref()) {
+                (Expression::Literal(_, left), Expression::Literal(_, right)) => {
+                    (*op_type, left.to_bits(), right.to_bits())
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    // only literals are left so the type doesn't depend on any variables
+    let value_type = expression.get_type(&Scope::new()).ok()?;
+
+    let value = match op_type {
+        Add => left.wrapping_add(right),
+        Subtract => left.wrapping_sub(right),
+        Multiply => left.wrapping_mul(right),
+        Divide => left.checked_div(right)?,
+        Equal => (left == right) as u64,
+        NotEqual => (left != right) as u64,
+        LessThan => (left < right) as u64,
+        LessThanEqual => (left <= right) as u64,
+        GreaterThan => (left > right) as u64,
+        GreaterThanEqual => (left >= right) as u64,
+    };
+
+    match value_type {
+        DynoType::Bool() => Some(Expression::Literal(value_type, DynoValue::Bool(value != 0))),
+        _ => Some(Expression::Literal(
+            value_type,
+            DynoValue::UInt(value_type.wrap(value)),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::interp;
+    use crate::error::DynoResult;
+    use crate::lexer::lex;
+    use crate::parser::parse;
+
+    fn literal(value_type: DynoType, value: u64) -> Expression {
+        Expression::Literal(value_type, DynoValue::UInt(value))
+    }
+
+    #[test]
+    fn fold_wraps_at_type_size() {
+        let expression = Expression::BinaryOperation(
+            BinaryOperationType::Add,
+            Box::new(literal(DynoType::UInt8(), 255)),
+            Box::new(literal(DynoType::UInt8(), 1)),
+        );
+
+        assert_eq!(fold_expression(expression), literal(DynoType::UInt8(), 0));
+    }
+
+    #[test]
+    fn fold_through_widen() -> DynoResult<()> {
+        let ast = fold_constants(parse(lex("let a: u32; a = 200 + 100; return a;")?)?);
+
+        assert_eq!(
+            ast,
+            Statement::Block(vec![
+                Statement::Declaration("a".to_string(), DynoType::UInt32()),
+                Statement::Assignment("a".to_string(), literal(DynoType::UInt32(), 300)),
+                Statement::Return(Expression::Identifier("a".to_string())),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fold_comparison_and_division() {
+        let comparison = Expression::BinaryOperation(
+            BinaryOperationType::LessThan,
+            Box::new(literal(DynoType::UInt8(), 2)),
+            Box::new(literal(DynoType::UInt8(), 5)),
+        );
+        assert_eq!(
+            fold_expression(comparison),
+            Expression::Literal(DynoType::Bool(), DynoValue::Bool(true))
+        );
+
+        let division = Expression::BinaryOperation(
+            BinaryOperationType::Divide,
+            Box::new(literal(DynoType::UInt8(), 2)),
+            Box::new(literal(DynoType::UInt8(), 0)),
+        );
+        assert!(matches!(
+            fold_expression(division),
+            Expression::BinaryOperation(..)
+        ));
+    }
+
+    #[test]
+    fn fold_literal_comparisons() -> DynoResult<()> {
+        assert_eq!(
+            fold_constants(parse(lex("return 1 == 1;")?)?),
+            Statement::Return(Expression::Literal(DynoType::Bool(), DynoValue::Bool(true)))
+        );
+        assert_eq!(
+            fold_constants(parse(lex("return 3 < 2;")?)?),
+            Statement::Return(Expression::Literal(
+                DynoType::Bool(),
+                DynoValue::Bool(false)
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn optimize_constant_if() -> DynoResult<()> {
+        let ast = optimize(parse(lex("if 1 == 0 { return 5; } return 7;")?)?);
+        assert_eq!(ast, Statement::Return(literal(DynoType::UInt8(), 7)));
+        assert_eq!(interp::run(&ast)?, 7);
+
+        let ast = optimize(parse(lex(
+            "let a: u8; if 1 == 1 { a = 1; } else { a = 2; }",
+        )?)?);
+        assert_eq!(
+            ast,
+            Statement::Block(vec![
+                Statement::Declaration("a".to_string(), DynoType::UInt8()),
+                Statement::Assignment("a".to_string(), literal(DynoType::UInt8(), 1)),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn optimize_keeps_declaration_scope() -> DynoResult<()> {
+        let ast = optimize(parse(lex(
+            "let a: u8; if true { let a: u16; a = 1; } a = 2;",
+        )?)?);
+        assert_eq!(
+            ast,
+            Statement::Block(vec![
+                Statement::Declaration("a".to_string(), DynoType::UInt8()),
+                Statement::Block(vec![
+                    Statement::Declaration("a".to_string(), DynoType::UInt16()),
+                    Statement::Assignment("a".to_string(), literal(DynoType::UInt16(), 1)),
+                ]),
+                Statement::Assignment("a".to_string(), literal(DynoType::UInt8(), 2)),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn optimize_while_false() -> DynoResult<()> {
+        let ast = optimize(parse(lex("let a: u8; while 2 < 1 { a = 1; } return 3;")?)?);
+        assert_eq!(
+            ast,
+            Statement::Block(vec![
+                Statement::Declaration("a".to_string(), DynoType::UInt8()),
+                Statement::Return(literal(DynoType::UInt8(), 3)),
+            ])
+        );
+        Ok(())
+    }
+}
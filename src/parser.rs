@@ -1,21 +1,123 @@
-use crate::ast::{BinaryOperationType, Expression, Statement};
+use crate::ast::{BinaryOperationType, Builtin, CastType, Comments, Expression, Statement};
 use crate::error::*;
+use crate::optimizer::fold_expression;
 use crate::scope::Scope;
 use crate::token::{Token, TokenType};
 use crate::types::{DynoType, DynoValue};
+use std::ops::Range;
+
+/// Options which change the shape of the ast produced by the parser.
+///
+/// When `collapse_blocks` is set, a block containing a single statement is replaced by that statement.
+/// `max_depth` limits how deep expressions and blocks can be nested, deeper input results in an
+/// error instead of overflowing the stack.
+/// When `default_int_type` is set, integer literals get that type, or the smallest wider type they
+/// fit in, unless they are used together with a narrower integer. Otherwise they get the smallest
+/// type which can hold them.
+/// When `newline_terminators` is set, a line break or the end of the input ends a statement like a
+/// semicolon does. This needs the whitespace tokens, so the input has to be lexed with
+/// `Lexer::keep_whitespace`.
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    pub collapse_blocks: bool,
+    pub max_depth: usize,
+    pub default_int_type: Option<DynoType>,
+    pub newline_terminators: bool,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            collapse_blocks: false,
+            max_depth: 256,
+            default_int_type: None,
+            newline_terminators: false,
+        }
+    }
+}
 
 struct Parser {
     tokens: Vec<Token>,
+    /// The comments in front of each token, comment tokens themselves are removed from `tokens`.
+    /// There is one extra entry for the comments after the last token.
+    comments: Vec<Vec<String>>,
+    /// The trailing comment on the line of each token.
+    trailing: Vec<Option<String>>,
+    /// Whether there is a line break in front of each token, whitespace tokens are removed.
+    newlines: Vec<bool>,
     index: usize,
     variable_scope: Scope<DynoType>,
+    /// The values of the named constants, these share their names with the variables.
+    constant_scope: Scope<(DynoType, DynoValue)>,
+    return_type: Option<DynoType>,
+    /// The amount of expressions and blocks currently being parsed.
+    depth: usize,
+    /// The labels of the loops around the current statement, the innermost loop comes last.
+    loops: Vec<Option<String>>,
+    config: ParserConfig,
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
+    #[cfg(test)]
+    fn new(input: Vec<Token>) -> Self {
+        Self::with_config(input, ParserConfig::default())
+    }
+
+    /// Whitespace tokens are only removed when newlines end statements, otherwise they are kept
+    /// as is.
+    fn with_config(input: Vec<Token>, config: ParserConfig) -> Self {
+        let mut tokens = vec![];
+        let mut comments = vec![];
+        let mut trailing: Vec<Option<String>> = vec![];
+        let mut newlines = vec![];
+        let mut pending = vec![];
+        let mut newline = false;
+
+        for token in input {
+            match token.token_type {
+                TokenType::Comment => {
+                    // a block comment can contain the line break which ends a statement
+                    newline |= config.newline_terminators && token.value.contains('\n');
+                    pending.push(token.value.trim_end().to_string())
+                }
+                TokenType::TrailingComment => match trailing.last_mut() {
+                    Some(comment) => *comment = Some(token.value.trim_end().to_string()),
+                    None => pending.push(token.value.trim_end().to_string()),
+                },
+                // a line comment always ends in a line break
+                TokenType::Whitespace if config.newline_terminators => {
+                    newline |= token.contains_newline()
+                }
+                _ => {
+                    tokens.push(token);
+                    comments.push(std::mem::take(&mut pending));
+                    trailing.push(None);
+                    newlines.push(std::mem::take(&mut newline));
+                }
+            }
+        }
+        comments.push(pending);
+
         Self {
             tokens,
+            comments,
+            trailing,
+            newlines,
             index: 0,
             variable_scope: Scope::new(),
+            constant_scope: Scope::new(),
+            return_type: None,
+            depth: 0,
+            loops: vec![],
+            config,
+        }
+    }
+
+    /// Takes the comments in front of the current token, or after the last token at the end.
+    fn take_comments(&mut self) -> Vec<String> {
+        match self.comments.get_mut(self.index) {
+            Some(comments) => std::mem::take(comments),
+            None => vec![],
         }
     }
 
@@ -27,7 +129,6 @@ impl Parser {
         Ok(&self.tokens[self.index])
     }
 
-    #[allow(dead_code)]
     fn peek_next(&self, index: usize) -> DynoResult<&Token> {
         if self.index + index >= self.tokens.len() {
             return Err(DynoError::TokenStreamOutOfBounds());
@@ -63,20 +164,54 @@ impl Parser {
         self.index >= self.tokens.len()
     }
 
+    /// Returns whether the next token is on a new line when newlines end statements, the end of
+    /// the input counts as a new line.
+    fn at_line_end(&self) -> bool {
+        self.config.newline_terminators && self.newlines.get(self.index).copied().unwrap_or(true)
+    }
+
+    /// Consumes the semicolon at the end of a statement. When newlines end statements, a line
+    /// break or the closing brace of the block can replace it.
+    fn consume_terminator(&mut self) -> DynoResult<()> {
+        if !self.is_eof() && self.peek()?.token_type == TokenType::SemiColon {
+            self.consume()?;
+            return Ok(());
+        }
+        if self.at_line_end()
+            || (self.config.newline_terminators && self.peek()?.token_type == TokenType::RightBrace)
+        {
+            return Ok(());
+        }
+
+        self.consume_expect(TokenType::SemiColon)?;
+        Ok(())
+    }
+
+    /// Returns the source span from the token at `start` up to the last consumed token.
+    fn span_from(&self, start: usize) -> Range<usize> {
+        match (
+            self.tokens.get(start),
+            self.tokens.get(self.index.wrapping_sub(1)),
+        ) {
+            (Some(first), Some(last)) if self.index > start => first.span.start..last.span.end,
+            (Some(first), _) => first.span.clone(),
+            _ => 0..0,
+        }
+    }
+
     fn parse_integer_literal(&mut self) -> DynoResult<Expression> {
         let token = self.consume_expect(TokenType::IntegerLiteral)?;
 
         let value = token.value.parse::<u64>();
         match value {
             Ok(value) => {
-                let mut value_type = DynoType::UInt64();
-                if value < 2_u64.pow(8) {
-                    value_type = DynoType::UInt8();
-                } else if value < 2_u64.pow(16) {
-                    value_type = DynoType::UInt16();
-                } else if value < 2_u64.pow(32) {
-                    value_type = DynoType::UInt32();
-                }
+                // literals get the smallest type which can hold them
+                let min_bits = self.config.default_int_type.map_or(0, |x| x.get_bits());
+                let value_type = [DynoType::UInt8(), DynoType::UInt16(), DynoType::UInt32()]
+                    .iter()
+                    .copied()
+                    .find(|x| x.get_bits() >= min_bits && x.fits(value))
+                    .unwrap_or(DynoType::UInt64());
 
                 Ok(Expression::Literal(value_type, DynoValue::UInt(value)))
             }
@@ -84,6 +219,16 @@ impl Parser {
         }
     }
 
+    fn parse_bool_literal(&mut self) -> DynoResult<Expression> {
+        let token = self.consume_expect(TokenType::BoolLiteral)?;
+        let value = token.value == "true";
+
+        Ok(Expression::Literal(
+            DynoType::Bool(),
+            DynoValue::Bool(value),
+        ))
+    }
+
     fn parse_identifier(&mut self) -> DynoResult<String> {
         let token = self.consume_expect(TokenType::Identifier)?;
         Ok(token.value.clone())
@@ -96,32 +241,261 @@ impl Parser {
 
         match next.token_type {
             IntegerLiteral => self.parse_integer_literal(),
+            BoolLiteral => self.parse_bool_literal(),
             LeftParen => {
                 self.consume_expect(LeftParen)?;
                 let expression = self.parse_expression(0)?;
                 self.consume_expect(RightParen)?;
                 Ok(expression)
             }
-            Identifier => Ok(Expression::Identifier(self.parse_identifier()?)),
+            // a newline can end the input right after the identifier
+            Identifier if matches!(self.peek_next(1), Ok(x) if x == Equals) => {
+                self.parse_assignment_expression()
+            }
+            Identifier if matches!(self.peek_next(1), Ok(x) if x == LeftParen) => self.parse_call(),
+            Identifier => {
+                let identifier = self.parse_identifier()?;
+                match self.constant_scope.find(&identifier) {
+                    Ok((value_type, value)) => Ok(Expression::Literal(value_type, value)),
+                    Err(_) => Ok(Expression::Identifier(identifier)),
+                }
+            }
+            LeftBrace => self.parse_block_expression(),
             _ => Err(DynoError::UnexpectedTokenError(
                 next.token_type,
-                vec![IntegerLiteral, LeftParen, Identifier],
+                vec![
+                    IntegerLiteral,
+                    BoolLiteral,
+                    LeftParen,
+                    Identifier,
+                    LeftBrace,
+                ],
             )),
         }
     }
 
+    fn parse_call(&mut self) -> DynoResult<Expression> {
+        let name = self.parse_identifier()?;
+        let builtin = Builtin::from_name(&name)
+            .ok_or_else(|| DynoError::IdentifierError(format!("Unknown function `{}`", name)))?;
+
+        self.consume_expect(TokenType::LeftParen)?;
+        let mut arguments = vec![];
+        for _ in 0..builtin.parameter_count() {
+            let start = self.index;
+            let argument = self.parse_expression(0)?;
+            // every builtin with parameters takes integers
+            let argument_type = argument.get_type(&self.variable_scope)?;
+            if !argument_type.is_int() {
+                return Err(DynoError::IncompatibleTypeError(
+                    DynoType::UInt64(),
+                    argument_type,
+                    Some(self.span_from(start)),
+                ));
+            }
+            arguments.push((argument, argument_type));
+        }
+        self.consume_expect(TokenType::RightParen)?;
+
+        Ok(Expression::Call(builtin, arguments))
+    }
+
+    fn parse_block_expression(&mut self) -> DynoResult<Expression> {
+        use TokenType::*;
+
+        self.consume_expect(LeftBrace)?;
+
+        self.variable_scope.push();
+        self.constant_scope.push();
+        // jumping out of a loop halfway through an expression isn't supported
+        let loops = std::mem::take(&mut self.loops);
+
+        let mut statements = vec![];
+        loop {
+            let is_statement = match self.peek()?.token_type {
+                Let | Const | While | Do | Return | If | Assert | Break | Continue | LeftBrace => {
+                    true
+                }
+                Identifier => matches!(self.peek_next(1)?.token_type, Equals | Colon),
+                _ => false,
+            };
+
+            if !is_statement {
+                break;
+            }
+
+            statements.push(self.parse_statement()?);
+        }
+
+        // the last expression without a semicolon is the value of the block
+        let expression = self.parse_expression(0)?;
+        self.loops = loops;
+
+        self.variable_scope.pop()?;
+        self.constant_scope.pop()?;
+
+        self.consume_expect(RightBrace)?;
+        Ok(Expression::Block(statements, Box::new(expression)))
+    }
+
+    /// Parses a primary expression followed by any amount of casts.
+    ///
+    /// `as` and `as!` are postfix operators which bind tighter than every binary operator, so
+    /// `1 + 2 as u8` is parsed as `1 + (2 as u8)`. Chained casts are applied from left to right.
     fn parse_unary_expression(&mut self) -> DynoResult<Expression> {
-        self.parse_primary_expression()
+        let expression = self.parse_primary_expression()?;
+        self.parse_postfix_operators(expression)
     }
 
+    /// Parses the casts and method calls after an expression, this is kept out of
+    /// `parse_unary_expression` so nested expressions don't need more stack.
+    fn parse_postfix_operators(&mut self, mut expression: Expression) -> DynoResult<Expression> {
+        loop {
+            if self.at_line_end() {
+                return Ok(expression);
+            }
+
+            let cast_type = match self.peek()?.token_type {
+                TokenType::As => CastType::Truncate,
+                TokenType::AsSaturating => CastType::Saturate,
+                TokenType::Dot => {
+                    expression = self.parse_method_call(expression)?;
+                    continue;
+                }
+                _ => return Ok(expression),
+            };
+            self.consume()?;
+
+            let value_type = self.parse_type()?;
+            let expression_type = expression.get_type(&self.variable_scope)?;
+            if !value_type.is_int() || !expression_type.is_int() {
+                return Err(DynoError::IncompatibleTypeError(
+                    value_type,
+                    expression_type,
+                    None,
+                ));
+            }
+
+            expression = Expression::Cast(Box::new(expression), value_type, cast_type);
+        }
+    }
+
+    /// Parses a method call like `.wrapping_add(b)` on an integer receiver, the argument gets the
+    /// type of the receiver.
+    fn parse_method_call(&mut self, receiver: Expression) -> DynoResult<Expression> {
+        self.consume_expect(TokenType::Dot)?;
+        let name = self.parse_identifier()?;
+        let builtin = Builtin::from_method(&name)
+            .ok_or_else(|| DynoError::IdentifierError(format!("Unknown method `{}`", name)))?;
+
+        let receiver_type = receiver.get_type(&self.variable_scope)?;
+        if !receiver_type.is_int() {
+            return Err(DynoError::IncompatibleTypeError(
+                DynoType::UInt64(),
+                receiver_type,
+                None,
+            ));
+        }
+
+        self.consume_expect(TokenType::LeftParen)?;
+        let start = self.index;
+        let argument = self.parse_expression(0)?;
+        let argument =
+            Expression::make_assignment_compatible(receiver_type, argument, &self.variable_scope)
+                .map_err(|error| error.with_span(self.span_from(start)))?;
+        self.consume_expect(TokenType::RightParen)?;
+
+        Ok(Expression::Call(
+            builtin,
+            vec![(receiver, receiver_type), (argument, receiver_type)],
+        ))
+    }
+
+    fn enter_nested(&mut self) -> DynoResult<()> {
+        if self.depth >= self.config.max_depth {
+            return Err(DynoError::DepthLimitError(self.config.max_depth));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Parses an expression, type errors inside of it get the span of the innermost expression
+    /// which contains them.
     fn parse_expression(&mut self, precendence: u8) -> DynoResult<Expression> {
-        const DELIMETERS: [TokenType; 3] = [
+        self.enter_nested()?;
+        let start = self.index;
+        let expression = self.parse_conditional_expression(precendence);
+        self.depth -= 1;
+        expression.map_err(|error| error.with_span(self.span_from(start)))
+    }
+
+    fn parse_conditional_expression(&mut self, precendence: u8) -> DynoResult<Expression> {
+        let (condition, condition_type) = self.parse_binary_expression(precendence)?;
+        if self.at_line_end() || self.peek()?.token_type != TokenType::Question {
+            return Ok(condition);
+        }
+
+        self.consume_expect(TokenType::Question)?;
+        let true_expression = self.parse_expression(0)?;
+        self.consume_expect(TokenType::Colon)?;
+        let false_expression = self.parse_expression(0)?;
+
+        if condition_type != DynoType::Bool() {
+            return Err(DynoError::IncompatibleTypeError(
+                DynoType::Bool(),
+                condition_type,
+                None,
+            ));
+        }
+
+        // integer arms get widened to the widest of the two
+        let true_type = true_expression.get_type(&self.variable_scope)?;
+        let false_type = false_expression.get_type(&self.variable_scope)?;
+        let result_type = if false_type.can_widen_to(&true_type) {
+            true_type
+        } else if true_type.can_widen_to(&false_type) {
+            false_type
+        } else {
+            return Err(DynoError::IncompatibleTypeError(
+                true_type, false_type, None,
+            ));
+        };
+
+        Ok(Expression::Conditional(
+            Box::new(condition),
+            Box::new(Expression::make_assignment_compatible(
+                result_type,
+                true_expression,
+                &self.variable_scope,
+            )?),
+            Box::new(Expression::make_assignment_compatible(
+                result_type,
+                false_expression,
+                &self.variable_scope,
+            )?),
+        ))
+    }
+
+    /// Parses a binary expression and returns it together with its type, this type is built up
+    /// from the types of the operands so the subexpressions don't have to be traversed again for
+    /// every operator.
+    fn parse_binary_expression(&mut self, precendence: u8) -> DynoResult<(Expression, DynoType)> {
+        const DELIMETERS: [TokenType; 6] = [
             TokenType::SemiColon,
             TokenType::RightParen,
             TokenType::LeftBrace,
+            TokenType::RightBrace,
+            TokenType::Question,
+            TokenType::Colon,
         ];
 
-        let mut left = self.parse_unary_expression()?;
+        let left = self.parse_unary_expression()?;
+        let left_type = left.get_type(&self.variable_scope)?;
+        let mut left = (left, left_type);
+        if self.at_line_end() {
+            return Ok(left);
+        }
 
         let mut operator = self.peek()?;
 
@@ -136,18 +510,12 @@ impl Parser {
             let token_type = operator.token_type;
             self.consume_expect(token_type)?;
 
-            let right = self.parse_expression(current_precendence)?;
-            let left_type = left.get_type(&self.variable_scope)?;
-            let right_type = right.get_type(&self.variable_scope)?;
-
-            left = Expression::make_binop_compatible(
-                operator_type,
-                left,
-                right,
-                &self.variable_scope,
-            )?
-            .ok_or(DynoError::IncompatibleTypeError(left_type, right_type))?;
+            let right = self.parse_binary_expression(current_precendence)?;
+            left = Expression::make_binop_compatible(operator_type, left, right)?;
 
+            if self.at_line_end() {
+                return Ok(left);
+            }
             operator = self.peek()?;
 
             if DELIMETERS.contains(&operator.token_type) {
@@ -186,55 +554,182 @@ impl Parser {
         self.consume_expect(TokenType::Colon)?;
 
         let variable_type = self.parse_type()?;
-        self.consume_expect(TokenType::SemiColon)?;
+        self.consume_terminator()?;
 
+        // variables and constants can't shadow each other, so a name always refers to the same
+        // kind of value
+        if self.constant_scope.find(&identifier).is_ok() {
+            return Err(DynoError::DuplicateDefinition(identifier));
+        }
         self.variable_scope.insert(&identifier, variable_type)?;
 
         Ok(Statement::Declaration(identifier, variable_type))
     }
 
-    fn parse_assignment(&mut self) -> DynoResult<Statement> {
+    /// Parses `const identifier: type = expression;`, the expression has to fold to a literal.
+    fn parse_constant(&mut self) -> DynoResult<Statement> {
+        self.consume_expect(TokenType::Const)?;
+
         let identifier = self.parse_identifier()?;
+        self.consume_expect(TokenType::Colon)?;
+        let constant_type = self.parse_type()?;
         self.consume_expect(TokenType::Equals)?;
 
+        let start = self.index;
         let expression = self.parse_expression(0)?;
-        self.consume_expect(TokenType::SemiColon)?;
+        let span = self.span_from(start);
+        self.consume_terminator()?;
+        let expression =
+            Expression::make_assignment_compatible(constant_type, expression, &self.variable_scope)
+                .map_err(|error| error.with_span(span))?;
+
+        let value = match fold_expression(expression) {
+            Expression::Literal(_, value) => value,
+            _ => {
+                return Err(DynoError::ConstantError(format!(
+                    "the value of `{}` isn't known at compile time",
+                    identifier
+                )))
+            }
+        };
+
+        if self.variable_scope.find(&identifier).is_ok() {
+            return Err(DynoError::DuplicateDefinition(identifier));
+        }
+        self.constant_scope
+            .insert(&identifier, (constant_type, value))?;
+
+        Ok(Statement::Constant(identifier, constant_type, value))
+    }
+
+    /// Parses `identifier = expression`, the right hand side can be another assignment.
+    fn parse_assignment_target(&mut self) -> DynoResult<(String, Expression)> {
+        let identifier = self.parse_identifier()?;
+        self.consume_expect(TokenType::Equals)?;
+
+        // assignment has the lowest precedence and is right associative
+        let start = self.index;
+        let expression = self.parse_expression(0)?;
+        let span = self.span_from(start);
+
+        if self.constant_scope.find(&identifier).is_ok() {
+            return Err(DynoError::ConstantError(format!(
+                "can't assign to `{}`",
+                identifier
+            )));
+        }
 
         let variable_type = self.variable_scope.find(&identifier)?;
+        let expression =
+            Expression::make_assignment_compatible(variable_type, expression, &self.variable_scope)
+                .map_err(|error| error.with_span(span))?;
 
-        Ok(Statement::Assignment(
-            identifier,
-            Expression::make_assignment_compatible(
-                variable_type,
-                expression,
-                &self.variable_scope,
-            )?,
-        ))
+        Ok((identifier, expression))
+    }
+
+    fn parse_assignment_expression(&mut self) -> DynoResult<Expression> {
+        let (identifier, expression) = self.parse_assignment_target()?;
+        Ok(Expression::Assign(identifier, Box::new(expression)))
+    }
+
+    fn parse_assignment(&mut self) -> DynoResult<Statement> {
+        let (identifier, expression) = self.parse_assignment_target()?;
+        self.consume_terminator()?;
+
+        Ok(Statement::Assignment(identifier, expression))
     }
 
     fn parse_return_statement(&mut self) -> DynoResult<Statement> {
         self.consume_expect(TokenType::Return)?;
+        let start = self.index;
         let expression = self.parse_expression(0)?;
-        self.consume_expect(TokenType::SemiColon)?;
+        let span = self.span_from(start);
+        self.consume_terminator()?;
+
+        // every return has to agree on the type, integers all get widened to the return register
+        let expression_type = expression.get_type(&self.variable_scope)?;
+        match self.return_type {
+            Some(return_type) if return_type.is_int() && expression_type.is_int() => {
+                // the program returns the widest of the integer types
+                if return_type.can_widen_to(&expression_type) {
+                    self.return_type = Some(expression_type);
+                }
+            }
+            Some(return_type) if return_type != expression_type => {
+                return Err(DynoError::IncompatibleTypeError(
+                    return_type,
+                    expression_type,
+                    Some(span),
+                ));
+            }
+            Some(_) => {}
+            None => self.return_type = Some(expression_type),
+        }
 
         Ok(Statement::Return(expression))
     }
 
+    fn parse_assert_statement(&mut self) -> DynoResult<Statement> {
+        self.consume_expect(TokenType::Assert)?;
+        self.consume_expect(TokenType::LeftParen)?;
+        let start = self.index;
+        let condition = self.parse_expression(0)?;
+        let span = self.span_from(start);
+        self.consume_expect(TokenType::RightParen)?;
+        self.consume_terminator()?;
+
+        let condition_type = condition.get_type(&self.variable_scope)?;
+        if condition_type != DynoType::Bool() {
+            return Err(DynoError::IncompatibleTypeError(
+                DynoType::Bool(),
+                condition_type,
+                Some(span),
+            ));
+        }
+
+        Ok(Statement::Assert(condition))
+    }
+
     fn parse_block(&mut self) -> DynoResult<Statement> {
+        self.enter_nested()?;
+        let block = self.parse_block_statements();
+        self.depth -= 1;
+        block
+    }
+
+    fn parse_block_statements(&mut self) -> DynoResult<Statement> {
         self.consume_expect(TokenType::LeftBrace)?;
 
         self.variable_scope.push();
+        self.constant_scope.push();
 
         let mut statements = vec![];
         while self.peek()?.token_type != TokenType::RightBrace {
             let statement = self.parse_statement()?;
             statements.push(statement);
         }
+        let after = self.take_comments();
+        attach_after(&mut statements, after);
 
         self.variable_scope.pop()?;
+        self.constant_scope.pop()?;
 
         self.consume_expect(TokenType::RightBrace)?;
-        if statements.len() == 1 {
+        // a declaration has to keep its block, otherwise it would end up in the enclosing scope
+        let collapsible = match statements.as_slice() {
+            [statement]
+                if matches!(
+                    statement.without_comments(),
+                    Statement::Declaration(_, _) | Statement::Constant(_, _, _)
+                ) =>
+            {
+                false
+            }
+            [_] => self.config.collapse_blocks,
+            _ => false,
+        };
+
+        if collapsible {
             Ok(statements.remove(0))
         } else {
             Ok(Statement::Block(statements))
@@ -245,41 +740,191 @@ impl Parser {
         self.consume_expect(TokenType::If)?;
         let condition = self.parse_expression(0)?;
         let true_node = self.parse_block()?;
-        Ok(Statement::If(condition, Box::new(true_node)))
+
+        if self.is_eof() || self.peek()?.token_type != TokenType::Else {
+            return Ok(Statement::If(condition, Box::new(true_node), None));
+        }
+
+        self.consume_expect(TokenType::Else)?;
+
+        // `else if` chains are parsed as an if statement inside of the else branch
+        let false_node = match self.peek()?.token_type {
+            TokenType::If => self.parse_if_statement()?,
+            _ => self.parse_block()?,
+        };
+
+        Ok(Statement::If(
+            condition,
+            Box::new(true_node),
+            Some(Box::new(false_node)),
+        ))
+    }
+
+    fn parse_while_statement(&mut self, label: Option<String>) -> DynoResult<Statement> {
+        self.consume_expect(TokenType::While)?;
+        let condition = self.parse_expression(0)?;
+
+        self.loops.push(label);
+        let body = self.parse_block();
+        self.loops.pop();
+
+        Ok(Statement::While(condition, Box::new(body?)))
     }
 
-    fn parse_while_statement(&mut self) -> DynoResult<Statement> {
+    fn parse_do_while_statement(&mut self, label: Option<String>) -> DynoResult<Statement> {
+        self.consume_expect(TokenType::Do)?;
+
+        self.loops.push(label);
+        let body = self.parse_block();
+        self.loops.pop();
+        let body = body?;
+
         self.consume_expect(TokenType::While)?;
         let condition = self.parse_expression(0)?;
-        let body = self.parse_block()?;
-        Ok(Statement::While(condition, Box::new(body)))
+        self.consume_terminator()?;
+
+        Ok(Statement::DoWhile(Box::new(body), condition))
+    }
+
+    fn parse_labeled_statement(&mut self) -> DynoResult<Statement> {
+        let label = self.parse_identifier()?;
+        self.consume_expect(TokenType::Colon)?;
+
+        let body = match self.peek()?.token_type {
+            TokenType::While => self.parse_while_statement(Some(label.clone()))?,
+            TokenType::Do => self.parse_do_while_statement(Some(label.clone()))?,
+            token_type => {
+                return Err(DynoError::UnexpectedTokenError(
+                    token_type,
+                    vec![TokenType::While, TokenType::Do],
+                ))
+            }
+        };
+        Ok(Statement::Labeled(label, Box::new(body)))
+    }
+
+    /// Parses `break` or `continue` with an optional label, which has to belong to an enclosing
+    /// loop.
+    fn parse_loop_control(&mut self) -> DynoResult<Statement> {
+        let keyword = self.consume()?.token_type;
+        // when newlines end statements, an identifier on the next line starts a new statement
+        let label = if !self.is_eof()
+            && !self.at_line_end()
+            && self.peek()?.token_type == TokenType::Identifier
+        {
+            Some(self.parse_identifier()?)
+        } else {
+            None
+        };
+        self.consume_terminator()?;
+
+        let keyword_name = match keyword {
+            TokenType::Break => "break",
+            _ => "continue",
+        };
+        if self.loops.is_empty() {
+            return Err(DynoError::ControlFlowError(format!(
+                "`{}` outside of a loop",
+                keyword_name
+            )));
+        }
+        if let Some(label) = &label {
+            if !self.loops.iter().any(|x| x.as_ref() == Some(label)) {
+                return Err(DynoError::ControlFlowError(format!(
+                    "`{} {}` doesn't refer to an enclosing loop",
+                    keyword_name, label
+                )));
+            }
+        }
+
+        match keyword {
+            TokenType::Break => Ok(Statement::Break(label)),
+            _ => Ok(Statement::Continue(label)),
+        }
     }
 
     fn parse_statement(&mut self) -> DynoResult<Statement> {
-        match self.peek()?.token_type {
+        let leading = self.take_comments();
+
+        let statement = match self.peek()?.token_type {
             TokenType::Let => self.parse_declaration(),
-            TokenType::While => self.parse_while_statement(),
+            TokenType::Const => self.parse_constant(),
+            TokenType::While => self.parse_while_statement(None),
+            TokenType::Do => self.parse_do_while_statement(None),
             TokenType::Return => self.parse_return_statement(),
             TokenType::If => self.parse_if_statement(),
+            TokenType::Assert => self.parse_assert_statement(),
+            TokenType::Break | TokenType::Continue => self.parse_loop_control(),
+            TokenType::Identifier if self.peek_next(1)?.token_type == TokenType::Colon => {
+                self.parse_labeled_statement()
+            }
             TokenType::Identifier => self.parse_assignment(),
             TokenType::LeftBrace => self.parse_block(),
             _ => Err(DynoError::UnexpectedTokenError(
                 self.peek()?.token_type,
                 vec![
                     TokenType::Let,
+                    TokenType::Const,
                     TokenType::While,
+                    TokenType::Do,
                     TokenType::Return,
                     TokenType::If,
+                    TokenType::Assert,
+                    TokenType::Break,
+                    TokenType::Continue,
                     TokenType::Identifier,
                     TokenType::LeftBrace,
                 ],
             )),
+        }?;
+
+        // every statement consumes at least one token
+        let comments = Comments {
+            leading,
+            trailing: self.trailing[self.index - 1].take(),
+            after: vec![],
+        };
+        if comments.is_empty() {
+            Ok(statement)
+        } else {
+            Ok(Statement::Commented(comments, Box::new(statement)))
         }
     }
 }
 
 pub fn parse(input: Vec<Token>) -> DynoResult<Statement> {
-    let mut parser = Parser::new(input);
+    parse_with_config(input, ParserConfig::default())
+}
+
+pub fn parse_with_config(input: Vec<Token>, config: ParserConfig) -> DynoResult<Statement> {
+    Ok(parse_program(input, config, &[])?.0)
+}
+
+/// Parses the input and returns the type of the value the program returns, this is `Void` for
+/// programs without a return statement.
+pub fn parse_with_return_type(input: Vec<Token>) -> DynoResult<(Statement, DynoType)> {
+    let (ast, return_type) = parse_program(input, ParserConfig::default(), &[])?;
+    Ok((ast, return_type.unwrap_or(DynoType::Void())))
+}
+
+/// Parses the body of a function, the parameters can be used like variables declared before it.
+pub fn parse_function_body(
+    input: Vec<Token>,
+    params: &[(String, DynoType)],
+) -> DynoResult<(Statement, DynoType)> {
+    let (ast, return_type) = parse_program(input, ParserConfig::default(), params)?;
+    Ok((ast, return_type.unwrap_or(DynoType::Void())))
+}
+
+fn parse_program(
+    input: Vec<Token>,
+    config: ParserConfig,
+    params: &[(String, DynoType)],
+) -> DynoResult<(Statement, Option<DynoType>)> {
+    let mut parser = Parser::with_config(input, config);
+    for (name, value_type) in params {
+        parser.variable_scope.insert(name, *value_type)?;
+    }
 
     let mut nodes: Vec<Statement> = vec![];
 
@@ -287,11 +932,38 @@ pub fn parse(input: Vec<Token>) -> DynoResult<Statement> {
         let node = parser.parse_statement()?;
         nodes.push(node);
     }
+    let after = parser.take_comments();
+    attach_after(&mut nodes, after);
 
-    Ok(match nodes.len() {
+    let ast = match nodes.len() {
         1 => nodes.remove(0),
         _ => Statement::Block(nodes),
-    })
+    };
+    Ok((ast, parser.return_type))
+}
+
+/// Attaches the comments at the end of a block or the program to its last statement, they are
+/// dropped when there are no statements.
+fn attach_after(statements: &mut Vec<Statement>, after: Vec<String>) {
+    if after.is_empty() {
+        return;
+    }
+
+    let statement = match statements.pop() {
+        Some(Statement::Commented(mut comments, statement)) => {
+            comments.after = after;
+            Statement::Commented(comments, statement)
+        }
+        Some(statement) => Statement::Commented(
+            Comments {
+                after,
+                ..Comments::default()
+            },
+            Box::new(statement),
+        ),
+        None => return,
+    };
+    statements.push(statement);
 }
 
 #[cfg(test)]
@@ -301,6 +973,7 @@ mod tests {
     use crate::ast::Expression::{BinaryOperation, Literal, Widen};
     use crate::ast::Statement::{Assignment, Block, Declaration, If, Return};
     use crate::lexer::lex;
+    use crate::optimizer::fold_constants;
     use crate::token::TokenType::*;
 
     #[test]
@@ -405,6 +1078,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_integer_literal_types() -> DynoResult<()> {
+        let expected = [
+            ("255", DynoType::UInt8()),
+            ("256", DynoType::UInt16()),
+            ("65536", DynoType::UInt32()),
+            ("4294967296", DynoType::UInt64()),
+        ];
+
+        for (input, value_type) in expected.iter() {
+            let mut parser = Parser::new(lex(input)?);
+            match parser.parse_integer_literal()? {
+                Literal(literal_type, _) => assert_eq!(literal_type, *value_type),
+                x => panic!("expected a literal, got {:?}", x),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bool_equality() -> DynoResult<()> {
+        assert_eq!(
+            get_statement("return true != false;")?,
+            Return(BinaryOperation(
+                BinaryOperationType::NotEqual,
+                Box::new(Literal(DynoType::Bool(), DynoValue::Bool(true))),
+                Box::new(Literal(DynoType::Bool(), DynoValue::Bool(false))),
+            ))
+        );
+        Ok(())
+    }
+
     #[test]
     fn parse_simple_declaration() -> DynoResult<()> {
         let ast = get_statement("let a: u32;")?;
@@ -429,10 +1134,7 @@ mod tests {
                 Declaration("a".to_string(), DynoType::UInt32()),
                 Assignment(
                     "a".to_string(),
-                    Widen(
-                        Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(12))),
-                        DynoType::UInt32()
-                    )
+                    Literal(DynoType::UInt32(), DynoValue::UInt(12))
                 )
             ])
         );
@@ -451,20 +1153,11 @@ mod tests {
                     "a".to_string(),
                     BinaryOperation(
                         BinaryOperationType::Subtract,
-                        Box::new(Widen(
-                            Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(12))),
-                            DynoType::UInt32()
-                        )),
+                        Box::new(Literal(DynoType::UInt32(), DynoValue::UInt(12))),
                         Box::new(BinaryOperation(
                             BinaryOperationType::Multiply,
-                            Box::new(Widen(
-                                Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(2))),
-                                DynoType::UInt32()
-                            )),
-                            Box::new(Widen(
-                                Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(4))),
-                                DynoType::UInt32()
-                            )),
+                            Box::new(Literal(DynoType::UInt32(), DynoValue::UInt(2))),
+                            Box::new(Literal(DynoType::UInt32(), DynoValue::UInt(4))),
                         ))
                     ),
                 )
@@ -473,6 +1166,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parser_chained_assignment() -> DynoResult<()> {
+        let ast = get_statement("let a: u32; let b: u8; a = b = 5;")?;
+
+        assert_eq!(
+            ast,
+            Block(vec![
+                Declaration("a".to_string(), DynoType::UInt32()),
+                Declaration("b".to_string(), DynoType::UInt8()),
+                Assignment(
+                    "a".to_string(),
+                    Widen(
+                        Box::new(Expression::Assign(
+                            "b".to_string(),
+                            Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(5)))
+                        )),
+                        DynoType::UInt32()
+                    )
+                )
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parser_chained_assignment_too_big_error() -> DynoResult<()> {
+        let result = get_statement("let a: u8; let b: u32; a = b = 5;");
+        assert_eq!(
+            result,
+            Err(DynoError::IncompatibleTypeError(
+                DynoType::UInt8(),
+                DynoType::UInt32(),
+                Some(27..32)
+            ))
+        );
+        Ok(())
+    }
+
     #[test]
     fn parse_simple_parentheses() -> DynoResult<()> {
         let ast = get_statement("return (12);")?;
@@ -510,7 +1241,120 @@ mod tests {
                     Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(1))),
                     Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(2)))
                 ),
-                Box::new(Return(Literal(DynoType::UInt8(), DynoValue::UInt(3))))
+                Box::new(Block(vec![Return(Literal(
+                    DynoType::UInt8(),
+                    DynoValue::UInt(3)
+                ))])),
+                None
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_body_scope() -> DynoResult<()> {
+        let config = ParserConfig {
+            collapse_blocks: true,
+            ..ParserConfig::default()
+        };
+
+        for input in &[
+            "if true { let t: u8; } t = 1;",
+            "if true { let t: u8; t = 1; } t = 1;",
+            "while false { let t: u8; } t = 1;",
+        ] {
+            assert!(matches!(
+                parse(lex(input)?),
+                Err(DynoError::IdentifierError(_))
+            ));
+            assert!(matches!(
+                parse_with_config(lex(input)?, config.clone()),
+                Err(DynoError::IdentifierError(_))
+            ));
+        }
+
+        let ast = parse_with_config(lex("let t: u8; if true { let t: u32; }")?, config)?;
+        assert_eq!(
+            ast,
+            Block(vec![
+                Declaration("t".to_owned(), DynoType::UInt8()),
+                If(
+                    Literal(DynoType::Bool(), DynoValue::Bool(true)),
+                    Box::new(Block(vec![Declaration("t".to_owned(), DynoType::UInt32())])),
+                    None
+                )
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_conditional() -> DynoResult<()> {
+        let ast = get_statement("let a: u16; return true ? a : 3;")?;
+
+        assert_eq!(
+            ast,
+            Block(vec![
+                Declaration("a".to_owned(), DynoType::UInt16()),
+                Return(Expression::Conditional(
+                    Box::new(Literal(DynoType::Bool(), DynoValue::Bool(true))),
+                    Box::new(Expression::Identifier("a".to_owned())),
+                    Box::new(Literal(DynoType::UInt16(), DynoValue::UInt(3)))
+                ))
+            ])
+        );
+
+        assert!(matches!(
+            get_statement("return 1 ? 2 : 3;"),
+            Err(DynoError::IncompatibleTypeError(_, _, _))
+        ));
+        assert!(matches!(
+            get_statement("return true ? 2 : false;"),
+            Err(DynoError::IncompatibleTypeError(_, _, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_collapse_blocks() -> DynoResult<()> {
+        let config = ParserConfig {
+            collapse_blocks: true,
+            ..ParserConfig::default()
+        };
+        let ast = parse_with_config(lex("if true { return 3; }")?, config)?;
+
+        assert_eq!(
+            ast,
+            If(
+                Literal(DynoType::Bool(), DynoValue::Bool(true)),
+                Box::new(Return(Literal(DynoType::UInt8(), DynoValue::UInt(3)))),
+                None
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_if_else() -> DynoResult<()> {
+        let ast =
+            get_statement("if true { return 1; } else if false { return 2; } else { return 3; }")?;
+        let return_block = |x| {
+            Box::new(Block(vec![Return(Literal(
+                DynoType::UInt8(),
+                DynoValue::UInt(x),
+            ))]))
+        };
+
+        assert_eq!(
+            ast,
+            If(
+                Literal(DynoType::Bool(), DynoValue::Bool(true)),
+                return_block(1),
+                Some(Box::new(If(
+                    Literal(DynoType::Bool(), DynoValue::Bool(false)),
+                    return_block(2),
+                    Some(return_block(3))
+                )))
             )
         );
         Ok(())
@@ -523,13 +1367,49 @@ mod tests {
             result,
             Block(vec![
                 Declaration("a".to_owned(), DynoType::UInt8()),
-                Declaration("a".to_owned(), DynoType::UInt32())
+                Block(vec![Declaration("a".to_owned(), DynoType::UInt32())])
             ])
         );
 
         Ok(())
     }
 
+    #[test]
+    fn parse_block_expression() -> DynoResult<()> {
+        let ast = get_statement("return { let t: u8; t = 3; t };")?;
+        assert_eq!(
+            ast,
+            Return(Expression::Block(
+                vec![
+                    Declaration("t".to_owned(), DynoType::UInt8()),
+                    Assignment(
+                        "t".to_owned(),
+                        Literal(DynoType::UInt8(), DynoValue::UInt(3))
+                    ),
+                ],
+                Box::new(Expression::Identifier("t".to_owned()))
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parser_multiple_returns() -> DynoResult<()> {
+        let result = get_statement("if 1 == 1 { return 1; } return 300;");
+        assert!(result.is_ok());
+
+        let result = get_statement("if 1 == 1 { return 1; } return 1 == 2;");
+        assert_eq!(
+            result,
+            Err(DynoError::IncompatibleTypeError(
+                DynoType::UInt8(),
+                DynoType::Bool(),
+                Some(31..37)
+            ))
+        );
+        Ok(())
+    }
+
     #[test]
     fn parser_consume_out_of_bounds_error() {
         let mut parser = Parser::new(vec![]);
@@ -609,7 +1489,354 @@ mod tests {
     #[test]
     fn parser_reassign_variable() -> DynoResult<()> {
         let result = parse(lex("{let a: u8; let a: u32;}")?);
-        assert!(result.is_err());
+        assert!(matches!(result, Err(DynoError::DuplicateDefinition(name)) if name == "a"));
+        Ok(())
+    }
+
+    #[test]
+    fn parser_nesting_depth_limit() -> DynoResult<()> {
+        let nested = |depth: usize, open: TokenType, inner: Vec<Token>, close: TokenType| {
+            let mut tokens: Vec<Token> = (0..depth).map(|_| Token::with_type(open)).collect();
+            tokens.extend(inner);
+            tokens.extend((0..depth).map(|_| Token::with_type(close)));
+            tokens
+        };
+        let parens = |depth| {
+            let mut tokens = vec![Token::with_type(TokenType::Return)];
+            tokens.extend(nested(
+                depth,
+                LeftParen,
+                vec![Token::new(IntegerLiteral, "1")],
+                RightParen,
+            ));
+            tokens.push(Token::with_type(SemiColon));
+            tokens
+        };
+
+        let result = parse(parens(100_000));
+        assert_eq!(result, Err(DynoError::DepthLimitError(256)));
+
+        let result = parse(nested(100_000, LeftBrace, vec![], RightBrace));
+        assert_eq!(result, Err(DynoError::DepthLimitError(256)));
+
+        assert!(parse(parens(200)).is_ok());
+        assert!(parse(nested(200, LeftBrace, vec![], RightBrace)).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_builtin_call() -> DynoResult<()> {
+        let ast = parse(lex("return read_int();")?)?;
+        assert_eq!(ast, Return(Expression::Call(Builtin::ReadInt, vec![])));
+
+        assert!(parse(lex("return read_int(1);")?).is_err());
+        assert_eq!(
+            parse(lex("return read_float();")?),
+            Err(DynoError::IdentifierError(
+                "Unknown function `read_float`".to_string()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_long_expression_type() -> DynoResult<()> {
+        // the narrow operands on the left get widened once the wider variable shows up
+        let terms: Vec<&str> = (0..1000).map(|i| if i < 600 { "a" } else { "b" }).collect();
+        let input = format!("let a: u8; let b: u32; return {};", terms.join(" + "));
+
+        let (ast, return_type) = parse_with_return_type(lex(&input)?)?;
+        assert_eq!(return_type, DynoType::UInt32());
+        match ast {
+            Block(statements) => match &statements[2] {
+                Return(expression) => {
+                    let mut scope = Scope::new();
+                    scope.insert("a", DynoType::UInt8())?;
+                    scope.insert("b", DynoType::UInt32())?;
+                    assert_eq!(expression.get_type(&scope)?, DynoType::UInt32());
+                }
+                x => panic!("expected a return, got {:?}", x),
+            },
+            x => panic!("expected a block, got {:?}", x),
+        }
+
+        let input = format!(
+            "let a: u8; let b: u32; return {} + true;",
+            terms.join(" + ")
+        );
+        assert!(matches!(
+            parse(lex(&input)?),
+            Err(DynoError::IncompatibleTypeError(_, DynoType::Bool(), _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_method_call() -> DynoResult<()> {
+        // the argument gets the type of the receiver and methods can be chained
+        let ast = get_statement("let a: u16; return a.wrapping_add(1).saturating_mul(2);")?;
+        let receiver = Expression::Call(
+            Builtin::WrappingAdd,
+            vec![
+                (Expression::Identifier("a".to_string()), DynoType::UInt16()),
+                (
+                    Literal(DynoType::UInt16(), DynoValue::UInt(1)),
+                    DynoType::UInt16(),
+                ),
+            ],
+        );
+        assert_eq!(
+            ast,
+            Block(vec![
+                Declaration("a".to_string(), DynoType::UInt16()),
+                Return(Expression::Call(
+                    Builtin::SaturatingMul,
+                    vec![
+                        (receiver, DynoType::UInt16()),
+                        (
+                            Literal(DynoType::UInt16(), DynoValue::UInt(2)),
+                            DynoType::UInt16()
+                        ),
+                    ],
+                ))
+            ])
+        );
+
+        // methods aren't functions and the argument can't be wider than the receiver
+        assert!(parse(lex("return wrapping_add(1);")?).is_err());
+        assert!(parse(lex("let a: u8; let b: u16; return a.wrapping_add(b);")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_widen_literal_retyped() -> DynoResult<()> {
+        let ast = get_statement("let a: u32; a = a + 5; return a < 300;")?;
+
+        assert_eq!(
+            ast,
+            Block(vec![
+                Declaration("a".to_owned(), DynoType::UInt32()),
+                Assignment(
+                    "a".to_owned(),
+                    BinaryOperation(
+                        Add,
+                        Box::new(Expression::Identifier("a".to_owned())),
+                        Box::new(Literal(DynoType::UInt32(), DynoValue::UInt(5))),
+                    )
+                ),
+                Return(BinaryOperation(
+                    BinaryOperationType::LessThan,
+                    Box::new(Expression::Identifier("a".to_owned())),
+                    Box::new(Literal(DynoType::UInt32(), DynoValue::UInt(300))),
+                ))
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cast_precedence() -> DynoResult<()> {
+        let literal = |x| Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(x)));
+        let cast = |x| Box::new(Expression::Cast(x, DynoType::UInt8(), CastType::Truncate));
+
+        assert_eq!(
+            get_statement("return 1 + 2 as u8;")?,
+            Return(BinaryOperation(Add, literal(1), cast(literal(2))))
+        );
+        assert_eq!(
+            get_statement("return (1 + 2) as u8;")?,
+            Return(*cast(Box::new(BinaryOperation(
+                Add,
+                literal(1),
+                literal(2)
+            ))))
+        );
+        assert_eq!(
+            get_statement("return 2 as u8 + 3;")?,
+            Return(BinaryOperation(Add, cast(literal(2)), literal(3)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_default_int_type() -> DynoResult<()> {
+        let config = ParserConfig {
+            default_int_type: Some(DynoType::UInt32()),
+            ..ParserConfig::default()
+        };
+
+        assert_eq!(
+            parse(lex("return 5;")?)?,
+            Return(Literal(DynoType::UInt8(), DynoValue::UInt(5)))
+        );
+        assert_eq!(
+            parse_with_config(lex("return 5;")?, config.clone())?,
+            Return(Literal(DynoType::UInt32(), DynoValue::UInt(5)))
+        );
+        assert_eq!(
+            parse_with_config(lex("return 4294967296;")?, config.clone())?,
+            Return(Literal(DynoType::UInt64(), DynoValue::UInt(4294967296)))
+        );
+
+        // literals used with a narrower integer still take on its type
+        let ast = parse_with_config(lex("let a: u8; a = 5; a = a + 5; return a;")?, config)?;
+        assert_eq!(
+            ast,
+            Block(vec![
+                Declaration("a".to_owned(), DynoType::UInt8()),
+                Assignment(
+                    "a".to_owned(),
+                    Literal(DynoType::UInt8(), DynoValue::UInt(5))
+                ),
+                Assignment(
+                    "a".to_owned(),
+                    BinaryOperation(
+                        Add,
+                        Box::new(Expression::Identifier("a".to_owned())),
+                        Box::new(Literal(DynoType::UInt8(), DynoValue::UInt(5)))
+                    )
+                ),
+                Return(Expression::Identifier("a".to_owned()))
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_constant_folded() -> DynoResult<()> {
+        let ast = fold_constants(parse(lex("const N: u8 = 5; return N * 2;")?)?);
+        assert_eq!(
+            ast,
+            Block(vec![
+                Statement::Constant("N".to_string(), DynoType::UInt8(), DynoValue::UInt(5)),
+                Return(Literal(DynoType::UInt8(), DynoValue::UInt(10))),
+            ])
+        );
+
+        // constants can be defined in terms of each other and get the declared type
+        assert_eq!(
+            get_statement("{ const A: u32 = 10; const B: u32 = A * A; return B; }")?,
+            Block(vec![
+                Statement::Constant("A".to_string(), DynoType::UInt32(), DynoValue::UInt(10)),
+                Statement::Constant("B".to_string(), DynoType::UInt32(), DynoValue::UInt(100)),
+                Return(Literal(DynoType::UInt32(), DynoValue::UInt(100))),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_constant_errors() -> DynoResult<()> {
+        assert_eq!(
+            parse(lex("let a: u8; const N: u8 = a;")?),
+            Err(DynoError::ConstantError(
+                "the value of `N` isn't known at compile time".to_string()
+            ))
+        );
+        assert_eq!(
+            parse(lex("const N: u8 = 5; N = 6;")?),
+            Err(DynoError::ConstantError("can't assign to `N`".to_string()))
+        );
+        assert_eq!(
+            parse(lex("const N: u8 = 5; { let N: u8; }")?),
+            Err(DynoError::DuplicateDefinition("N".to_string()))
+        );
+        assert_eq!(
+            parse(lex("const N: u8 = 300;")?),
+            Err(DynoError::IncompatibleTypeError(
+                DynoType::UInt8(),
+                DynoType::UInt16(),
+                Some(14..17)
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_type_error_span() -> DynoResult<()> {
+        let input = "let a: u8;\nreturn (a + true) * 2;";
+        let error = parse(lex(input)?).unwrap_err();
+        assert_eq!(
+            error,
+            DynoError::IncompatibleTypeError(DynoType::UInt8(), DynoType::Bool(), Some(19..27))
+        );
+        assert_eq!(&input[19..27], "a + true");
+        assert_eq!(
+            error.describe(input),
+            "Incompatible types UInt8 and Bool at 2:9"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bool_doesnt_widen() -> DynoResult<()> {
+        assert_eq!(
+            parse(lex("let a: u16; a = true;")?),
+            Err(DynoError::IncompatibleTypeError(
+                DynoType::UInt16(),
+                DynoType::Bool(),
+                Some(16..20)
+            ))
+        );
+        assert_eq!(
+            parse(lex("let a: bool; let b: u8; a = b;")?),
+            Err(DynoError::IncompatibleTypeError(
+                DynoType::Bool(),
+                DynoType::UInt8(),
+                Some(28..29)
+            ))
+        );
+        assert!(parse(lex("let a: u8; let b: u32; b = a;")?).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_newline_terminators() -> DynoResult<()> {
+        use crate::lexer::Lexer;
+
+        let config = ParserConfig {
+            newline_terminators: true,
+            ..ParserConfig::default()
+        };
+        let tokens = |input| Lexer::new(input).keep_whitespace(true).get_tokens();
+
+        assert_eq!(
+            parse_with_config(tokens("return 1\n")?, config.clone())?,
+            Return(Literal(DynoType::UInt8(), DynoValue::UInt(1)))
+        );
+        assert_eq!(
+            parse(lex("return 1\n")?),
+            Err(DynoError::TokenStreamOutOfBounds())
+        );
+
+        // semicolons still work, and an expression continues on the next line after an operator
+        let input = "let a: u8\nif a == 0 { a = 1 }\na = a +\n  2; return a\n";
+        let expected = "let a: u8; if a == 0 { a = 1; } a = a + 2; return a;";
+        assert_eq!(
+            parse_with_config(tokens(input)?, config.clone())?,
+            parse(lex(expected)?)?
+        );
+
+        assert_eq!(
+            parse_with_config(tokens("let a: u8 a = 1")?, config.clone()),
+            Err(DynoError::UnexpectedTokenError(Identifier, vec![SemiColon]))
+        );
+
+        let input =
+            "let x: u8\nouter: while true {\nwhile true {\nbreak\nx = 1\n}\ncontinue outer\n}";
+        let expected =
+            "let x: u8; outer: while true { while true { break; x = 1; } continue outer; }";
+        assert_eq!(
+            parse_with_config(tokens(input)?, config.clone())?,
+            parse(lex(expected)?)?
+        );
+
+        let input = "let a: u8 /* the\n value */ a = 1";
+        let tokens = Lexer::new(input)
+            .keep_whitespace(true)
+            .keep_comments(true)
+            .get_tokens()?;
+        assert!(parse_with_config(tokens, config).is_ok());
         Ok(())
     }
 }
@@ -1,6 +1,7 @@
 use crate::error::*;
 use std::collections::HashMap;
 
+#[derive(Clone)]
 pub struct Scope<T> {
     items: Vec<HashMap<String, T>>,
 }
@@ -16,14 +17,18 @@ where
     }
 
     pub fn insert(&mut self, name: &str, data: T) -> DynoResult<()> {
-        let scope_count = self.items.len();
-        let last_scope = &mut self.items[scope_count - 1];
+        let last_scope = match self.items.last_mut() {
+            Some(scope) => scope,
+            //TODO: Replace this with a better error type
+            None => {
+                return Err(DynoError::IdentifierError(
+                    "Tried inserting while scope stack was empty".to_owned(),
+                ))
+            }
+        };
 
         if last_scope.contains_key(name) {
-            return Err(DynoError::IdentifierError(format!(
-                "Identifier already defined in scope: {}",
-                name,
-            )));
+            return Err(DynoError::DuplicateDefinition(name.to_owned()));
         }
 
         last_scope.insert(name.to_owned(), data);
@@ -57,6 +62,38 @@ where
             name
         )))
     }
+
+    pub fn update(&mut self, name: &str, data: T) -> DynoResult<()> {
+        for scope in self.items.iter_mut().rev() {
+            if let Some(x) = scope.get_mut(name) {
+                *x = data;
+                return Ok(());
+            }
+        }
+
+        Err(DynoError::IdentifierError(format!(
+            "Identifier `{}` not found in scope",
+            name
+        )))
+    }
+
+    /// Returns the names declared in the innermost scope, the x86 backend uses this to free the
+    /// stack slots of a scope when leaving it.
+    pub fn current_names(&self) -> Vec<&str> {
+        match self.items.last() {
+            Some(scope) => scope.keys().map(|x| x.as_str()).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the names declared in all scopes, starting with the outermost scope. The x86 backend
+    /// sizes the stack frame with the most names which are in scope at the same time.
+    pub fn all_names(&self) -> Vec<&str> {
+        self.items
+            .iter()
+            .flat_map(|scope| scope.keys().map(|x| x.as_str()))
+            .collect()
+    }
 }
 
 impl<T> Default for Scope<T>
@@ -69,3 +106,65 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_names() -> DynoResult<()> {
+        let mut scope = Scope::new();
+        scope.insert("a", 1)?;
+        scope.insert("b", 2)?;
+        scope.push();
+        scope.insert("c", 3)?;
+        scope.insert("a", 4)?;
+
+        let mut current_names = scope.current_names();
+        current_names.sort_unstable();
+        assert_eq!(current_names, vec!["a", "c"]);
+
+        let mut all_names = scope.all_names();
+        all_names.sort_unstable();
+        assert_eq!(all_names, vec!["a", "a", "b", "c"]);
+
+        scope.pop()?;
+        let mut current_names = scope.current_names();
+        current_names.sort_unstable();
+        assert_eq!(current_names, vec!["a", "b"]);
+        Ok(())
+    }
+
+    #[test]
+    fn scope_duplicate_and_missing_errors() {
+        let mut scope = Scope::new();
+        assert!(scope.insert("a", 1).is_ok());
+
+        assert!(matches!(
+            scope.insert("a", 2),
+            Err(DynoError::DuplicateDefinition(name)) if name == "a"
+        ));
+        assert!(matches!(
+            scope.find("b"),
+            Err(DynoError::IdentifierError(_))
+        ));
+    }
+
+    #[test]
+    fn scope_update() -> DynoResult<()> {
+        let mut scope = Scope::new();
+        scope.insert("a", 1)?;
+        scope.push();
+        scope.insert("b", 2)?;
+
+        scope.update("a", 3)?;
+        scope.update("b", 4)?;
+        assert_eq!(scope.find("a")?, 3);
+        assert_eq!(scope.find("b")?, 4);
+        assert!(scope.update("c", 5).is_err());
+
+        scope.pop()?;
+        assert_eq!(scope.find("a")?, 3);
+        Ok(())
+    }
+}
@@ -1,13 +1,25 @@
+use std::fmt;
 use std::ops::Range;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TokenType {
     Whitespace,
+    Comment,
+    /// A comment at the end of a line with other tokens on it, see `formatter::format_source`.
+    TrailingComment,
 
     Let,
+    Const,
     While,
+    Do,
     Return,
     If,
+    Else,
+    Assert,
+    Break,
+    Continue,
+    As,
+    AsSaturating,
 
     UInt8,
     UInt16,
@@ -18,6 +30,7 @@ pub enum TokenType {
     Identifier,
 
     IntegerLiteral,
+    BoolLiteral,
 
     Plus,
     Minus,
@@ -33,6 +46,8 @@ pub enum TokenType {
     Equals,
 
     Colon,
+    Question,
+    Dot,
     SemiColon,
 
     LeftParen,
@@ -42,6 +57,60 @@ pub enum TokenType {
     RightBrace,
 }
 
+/// Keywords and symbols display as their source text, the other tokens display as a short name.
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TokenType::*;
+
+        let text = match *self {
+            Whitespace => "whitespace",
+            Comment => "comment",
+            TrailingComment => "trailing comment",
+            Let => "let",
+            Const => "const",
+            While => "while",
+            Do => "do",
+            Return => "return",
+            If => "if",
+            Else => "else",
+            Assert => "assert",
+            Break => "break",
+            Continue => "continue",
+            As => "as",
+            AsSaturating => "as!",
+            UInt8 => "u8",
+            UInt16 => "u16",
+            UInt32 => "u32",
+            UInt64 => "u64",
+            Bool => "bool",
+            Identifier => "ident",
+            IntegerLiteral => "int",
+            BoolLiteral => "bool_literal",
+            Plus => "+",
+            Minus => "-",
+            Asterix => "*",
+            Slash => "/",
+            DoubleEqual => "==",
+            NotEqual => "!=",
+            LessThan => "<",
+            LessThanEqual => "<=",
+            GreaterThan => ">",
+            GreaterThanEqual => ">=",
+            Equals => "=",
+            Colon => ":",
+            Question => "?",
+            Dot => ".",
+            SemiColon => ";",
+            LeftParen => "(",
+            RightParen => ")",
+            LeftBrace => "{",
+            RightBrace => "}",
+        };
+
+        write!(f, "{}", text)
+    }
+}
+
 #[derive(Debug)]
 pub struct Token {
     pub token_type: TokenType,
@@ -73,6 +142,32 @@ impl Token {
             span,
         }
     }
+
+    /// Returns whether this is a whitespace token which spans multiple lines.
+    pub fn contains_newline(&self) -> bool {
+        self.token_type == TokenType::Whitespace && self.value.contains('\n')
+    }
+}
+
+/// Displays as `type("value")@span`. The value is left out for keywords and symbols since it's
+/// always the same, and the span is left out for tokens which weren't lexed from a source.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TokenType::*;
+
+        write!(f, "{}", self.token_type)?;
+        if matches!(
+            self.token_type,
+            Whitespace | Comment | TrailingComment | Identifier | IntegerLiteral | BoolLiteral
+        ) {
+            write!(f, "({:?})", self.value)?;
+        }
+        if !self.span.is_empty() {
+            write!(f, "@{}..{}", self.span.start, self.span.end)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl PartialEq for Token {
@@ -86,3 +181,26 @@ impl PartialEq<TokenType> for &Token {
         self.token_type == *other
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_display() {
+        assert_eq!(TokenType::Plus.to_string(), "+");
+        assert_eq!(TokenType::AsSaturating.to_string(), "as!");
+        assert_eq!(
+            Token::new(TokenType::Identifier, "x").to_string(),
+            "ident(\"x\")"
+        );
+        assert_eq!(
+            Token::new_with_span(TokenType::IntegerLiteral, "12", 4..6).to_string(),
+            "int(\"12\")@4..6"
+        );
+        assert_eq!(
+            Token::new_with_span(TokenType::Plus, "+", 2..3).to_string(),
+            "+@2..3"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use crate::error::{DynoError, DynoResult};
+use std::fmt;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DynoType {
     UInt8(),
@@ -16,6 +19,34 @@ impl DynoType {
         )
     }
 
+    /// Returns whether a value of this type can be used as a value of the target type without a
+    /// cast. Integers widen to integers which are at least as wide, other types only to themselves.
+    pub fn can_widen_to(&self, target: &DynoType) -> bool {
+        self == target || (self.is_int() && target.is_int() && self.get_bits() <= target.get_bits())
+    }
+
+    /// Returns whether the value can be represented by this type without truncating it.
+    pub fn fits(&self, value: u64) -> bool {
+        match *self {
+            DynoType::UInt64() => true,
+            DynoType::UInt8() | DynoType::UInt16() | DynoType::UInt32() => {
+                value < 1 << self.get_bits()
+            }
+            DynoType::Bool() => value <= 1,
+            DynoType::Void() => false,
+        }
+    }
+
+    /// Truncates the value to the size of the type, the interpreter and the compiled code both
+    /// wrap the result of every arithmetic operation like this.
+    pub fn wrap(&self, value: u64) -> u64 {
+        match self.get_bits() {
+            0 => 0,
+            bits if bits >= 64 => value,
+            bits => value & ((1 << bits) - 1),
+        }
+    }
+
     pub fn get_bits(&self) -> u8 {
         match *self {
             DynoType::UInt8() => 8,
@@ -28,8 +59,111 @@ impl DynoType {
     }
 }
 
+impl fmt::Display for DynoType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            DynoType::UInt8() => "u8",
+            DynoType::UInt16() => "u16",
+            DynoType::UInt32() => "u32",
+            DynoType::UInt64() => "u64",
+            DynoType::Bool() => "bool",
+            DynoType::Void() => "void",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DynoValue {
     UInt(u64),
-    Bool(),
+    Bool(bool),
+}
+
+impl DynoValue {
+    pub fn as_u64(&self) -> DynoResult<u64> {
+        match *self {
+            DynoValue::UInt(x) => Ok(x),
+            DynoValue::Bool(_) => Err(DynoError::GeneratorError(
+                "Expected an integer value but got a bool".to_string(),
+            )),
+        }
+    }
+
+    pub fn as_bool(&self) -> DynoResult<bool> {
+        match *self {
+            DynoValue::Bool(x) => Ok(x),
+            DynoValue::UInt(_) => Err(DynoError::GeneratorError(
+                "Expected a bool value but got an integer".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the value as it is stored in a register, bools are either 0 or 1.
+    pub fn to_bits(&self) -> u64 {
+        match *self {
+            DynoValue::UInt(x) => x,
+            DynoValue::Bool(x) => x as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_fits() {
+        assert!(DynoType::UInt8().fits(255));
+        assert!(!DynoType::UInt8().fits(256));
+        assert!(DynoType::UInt16().fits(65535));
+        assert!(!DynoType::UInt16().fits(65536));
+        assert!(DynoType::UInt32().fits(4294967295));
+        assert!(!DynoType::UInt32().fits(4294967296));
+        assert!(DynoType::UInt64().fits(u64::MAX));
+        assert!(DynoType::Bool().fits(1));
+        assert!(!DynoType::Bool().fits(2));
+        assert!(!DynoType::Void().fits(0));
+    }
+
+    #[test]
+    fn types_can_widen_to() {
+        assert!(DynoType::UInt8().can_widen_to(&DynoType::UInt8()));
+        assert!(DynoType::UInt8().can_widen_to(&DynoType::UInt64()));
+        assert!(DynoType::UInt16().can_widen_to(&DynoType::UInt32()));
+        assert!(!DynoType::UInt32().can_widen_to(&DynoType::UInt16()));
+        assert!(!DynoType::UInt64().can_widen_to(&DynoType::UInt8()));
+
+        // bools have the size of a u8 but aren't integers
+        assert!(DynoType::Bool().can_widen_to(&DynoType::Bool()));
+        assert!(!DynoType::Bool().can_widen_to(&DynoType::UInt8()));
+        assert!(!DynoType::Bool().can_widen_to(&DynoType::UInt64()));
+        assert!(!DynoType::UInt8().can_widen_to(&DynoType::Bool()));
+        assert!(!DynoType::Void().can_widen_to(&DynoType::UInt8()));
+    }
+
+    #[test]
+    fn value_accessors() {
+        assert_eq!(DynoValue::UInt(12).as_u64().unwrap(), 12);
+        assert!(DynoValue::Bool(true).as_bool().unwrap());
+        assert!(matches!(
+            DynoValue::Bool(true).as_u64(),
+            Err(DynoError::GeneratorError(_))
+        ));
+        assert!(matches!(
+            DynoValue::UInt(1).as_bool(),
+            Err(DynoError::GeneratorError(_))
+        ));
+        assert_eq!(DynoValue::Bool(true).to_bits(), 1);
+        assert_eq!(DynoValue::UInt(7).to_bits(), 7);
+    }
+
+    #[test]
+    fn types_wrap() {
+        assert_eq!(DynoType::UInt8().wrap(256), 0);
+        assert_eq!(DynoType::UInt16().wrap(65537), 1);
+        assert_eq!(DynoType::UInt32().wrap(u64::MAX), u32::MAX as u64);
+        assert_eq!(DynoType::UInt64().wrap(u64::MAX), u64::MAX);
+        assert_eq!(DynoType::Void().wrap(5), 0);
+    }
 }
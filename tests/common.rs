@@ -1,3 +1,4 @@
+use dyno::backend::interp;
 use dyno::backend::x86_backend::compile_and_run;
 use dyno::error::DynoResult;
 use dyno::lexer::lex;
@@ -8,3 +9,10 @@ pub fn assert_run(input: &str, value: u64) -> DynoResult<()> {
     assert_eq!(result, value);
     Ok(())
 }
+
+/// Checks that the interpreter and the x86 backend agree on the result, not every test uses this.
+#[allow(dead_code)]
+pub fn assert_parity(input: &str, value: u64) -> DynoResult<()> {
+    assert_eq!(interp::run(&parse(lex(input)?)?)?, value);
+    assert_run(input, value)
+}
@@ -0,0 +1,62 @@
+use dyno::lexer::lex;
+use dyno::parser::parse;
+
+const INPUTS: [&str; 24] = [
+    "",
+    "\0",
+    "\0\0\0return 1;",
+    "🦖",
+    "return 🦖;",
+    "let 🦖: u8;",
+    "99999999999999999999999999999999",
+    "return 99999999999999999999999999999999;",
+    "{",
+    "}",
+    "{{{{{{",
+    "}}}}}}",
+    "return (1;",
+    "return 1);",
+    ")(",
+    "let",
+    "let a",
+    "let a: ;",
+    "a = ;",
+    "if",
+    "while {",
+    "return { 5",
+    "{ let a: u8; } a = 1;",
+    "return 1 +;",
+];
+
+fn lex_and_parse(input: &str) {
+    // errors are fine, we only care about not panicking
+    if let Ok(tokens) = lex(input) {
+        let _ = parse(tokens);
+    }
+}
+
+#[test]
+fn adversarial_inputs_do_not_panic() {
+    for input in INPUTS.iter() {
+        lex_and_parse(input);
+    }
+}
+
+#[test]
+fn random_bytes_do_not_panic() {
+    let alphabet = b"letwhilreturnifu8163264bool xyz0123456789+-*/=!<>:;(){}\0\xff\n";
+
+    // a small linear congruential generator keeps the test deterministic
+    let mut state: u64 = 0x5eed;
+    for _ in 0..500 {
+        let mut bytes = vec![];
+        for _ in 0..32 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            bytes.push(alphabet[(state >> 33) as usize % alphabet.len()]);
+        }
+
+        lex_and_parse(&String::from_utf8_lossy(&bytes));
+    }
+}
@@ -0,0 +1,53 @@
+use dyno::backend::x86_backend::{compile_and_run_at, compile_with_config, X86Config};
+use dyno::error::DynoResult;
+use dyno::lexer::lex;
+use dyno::parser::parse;
+use std::path::Path;
+
+#[test]
+fn artifacts_kept() -> DynoResult<()> {
+    std::fs::create_dir_all("target/artifacts")?;
+    let executable = "target/artifacts/kept.out";
+    let config = X86Config {
+        keep_artifacts: true,
+        ..X86Config::default()
+    };
+
+    compile_with_config(&parse(lex("return 3;")?)?, &config, executable)?;
+    assert!(Path::new(executable).exists());
+    assert!(Path::new("target/artifacts/kept.out.s").exists());
+    Ok(())
+}
+
+#[test]
+fn artifacts_removed() -> DynoResult<()> {
+    std::fs::create_dir_all("target/artifacts")?;
+    let executable = "target/artifacts/removed.out";
+
+    compile_with_config(
+        &parse(lex("return 3;")?)?,
+        &X86Config::default(),
+        executable,
+    )?;
+    assert!(Path::new(executable).exists());
+    assert!(!Path::new("target/artifacts/removed.out.s").exists());
+    Ok(())
+}
+
+#[test]
+fn artifacts_kept_after_running() -> DynoResult<()> {
+    std::fs::create_dir_all("target/artifacts")?;
+    let executable = "target/artifacts/ran.out";
+    let config = X86Config {
+        keep_artifacts: true,
+        ..X86Config::default()
+    };
+
+    assert_eq!(
+        compile_and_run_at(&parse(lex("return 3;")?)?, &config, executable)?,
+        3
+    );
+    assert!(Path::new(executable).exists());
+    assert!(Path::new("target/artifacts/ran.out.s").exists());
+    Ok(())
+}
@@ -0,0 +1,33 @@
+mod common;
+use common::assert_run;
+
+use dyno::backend::interp;
+use dyno::backend::x86_backend::compile_and_run;
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::parser::parse;
+
+#[test]
+fn assert_passes() -> DynoResult<()> {
+    assert_run("assert(1 == 1); return 7;", 7)?;
+    assert_eq!(interp::run(&parse(lex("assert(1 == 1); return 7;")?)?)?, 7);
+    Ok(())
+}
+
+#[test]
+fn assert_fails() -> DynoResult<()> {
+    let ast = parse(lex("assert(1 == 2); return 7;")?)?;
+    assert!(compile_and_run(&ast).is_err());
+    assert!(interp::run(&ast).is_err());
+    Ok(())
+}
+
+#[test]
+fn assert_requires_bool() -> DynoResult<()> {
+    let result = parse(lex("assert(1); return 7;")?);
+    assert!(matches!(
+        result,
+        Err(DynoError::IncompatibleTypeError(_, _, _))
+    ));
+    Ok(())
+}
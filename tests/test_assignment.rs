@@ -0,0 +1,43 @@
+mod common;
+use common::assert_run;
+
+use dyno::error::DynoResult;
+
+#[test]
+fn execute_chained_assignment() -> DynoResult<()> {
+    assert_run(
+        r"
+        let a: u32;
+        let b: u32;
+        a = b = 5;
+        return a * 10 + b;",
+        55,
+    )
+}
+
+#[test]
+fn execute_chained_assignment_wraps() -> DynoResult<()> {
+    assert_run(
+        r"
+        let a: u32;
+        let b: u8;
+        a = b = 255 + 2;
+        return a;",
+        1,
+    )
+}
+
+#[test]
+fn execute_assignment_in_condition() -> DynoResult<()> {
+    // assignments are allowed inside of conditions, but need parentheses around them to be compared
+    assert_run(
+        r"
+        let a: u32;
+        a = 0;
+        if (a = 3) == 3 {
+            return a;
+        }
+        return 0;",
+        3,
+    )
+}
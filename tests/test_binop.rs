@@ -1,7 +1,10 @@
 mod common;
 use common::assert_run;
 
+use dyno::backend::x86_backend::{compile_and_run_with_config, X86Config};
 use dyno::error::DynoResult;
+use dyno::lexer::lex;
+use dyno::parser::parse;
 
 #[test]
 fn execute_single_int() -> DynoResult<()> {
@@ -37,3 +40,21 @@ fn execute_divide_expression() -> DynoResult<()> {
 fn execute_complete_expression() -> DynoResult<()> {
     assert_run("return 12 / 3 + 7 * 8 - 10 / 2 * 4;", 40)
 }
+
+#[test]
+fn execute_minimize_registers() -> DynoResult<()> {
+    let config = X86Config {
+        minimize_registers: true,
+        ..X86Config::default()
+    };
+
+    for (input, value) in &[
+        ("return 1 + (2 + (3 + (4 + 5)));", 15),
+        ("return 100 - (2 * (3 + (4 - 1)));", 88),
+        ("return 60 / (2 + (1 * (2 + 1)));", 12),
+    ] {
+        let ast = parse(lex(input)?)?;
+        assert_eq!(compile_and_run_with_config(&ast, &config)?, *value);
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+mod common;
+use common::assert_run;
+
+use dyno::error::DynoResult;
+
+#[test]
+fn execute_block_expression() -> DynoResult<()> {
+    assert_run(
+        r"
+        return {
+            let t: u32;
+            t = 5;
+            t * 2
+        };",
+        10,
+    )
+}
+
+#[test]
+fn execute_block_expression_assignment() -> DynoResult<()> {
+    assert_run(
+        r"
+        let x: u32;
+        x = 3;
+        let y: u32;
+        y = { let t: u32; t = x + 4; t * x };
+        return y + 1;",
+        22,
+    )
+}
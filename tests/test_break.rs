@@ -0,0 +1,111 @@
+mod common;
+use common::assert_parity;
+
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::parser::parse;
+
+#[test]
+fn break_innermost_loop() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u32;
+        a = 0;
+        while true {
+            a = a + 1;
+            if a == 5 {
+                break;
+            }
+        }
+        return a;",
+        5,
+    )
+}
+
+#[test]
+fn continue_skips_rest_of_body() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let i: u32;
+        let sum: u32;
+        i = 0;
+        sum = 0;
+        while i < 10 {
+            i = i + 1;
+            if i > 3 {
+                continue;
+            }
+            sum = sum + i;
+        }
+        return sum;",
+        6,
+    )
+}
+
+#[test]
+fn break_labeled_outer_loop() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let i: u32;
+        let j: u32;
+        let result: u32;
+        i = 0;
+        result = 0;
+        outer: while i < 10 {
+            j = 0;
+            while j < 10 {
+                if (i * j) == 12 {
+                    result = i * 10 + j;
+                    break outer;
+                }
+                j = j + 1;
+            }
+            i = i + 1;
+        }
+        return result;",
+        26,
+    )
+}
+
+#[test]
+fn continue_labeled_outer_loop() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let i: u32;
+        let j: u32;
+        let count: u32;
+        i = 0;
+        count = 0;
+        outer: while i < 4 {
+            i = i + 1;
+            j = 0;
+            while true {
+                j = j + 1;
+                count = count + 1;
+                if j == i {
+                    continue outer;
+                }
+            }
+        }
+        return count;",
+        10,
+    )
+}
+
+#[test]
+fn break_outside_loop_error() -> DynoResult<()> {
+    assert_eq!(
+        parse(lex("break;")?),
+        Err(DynoError::ControlFlowError(
+            "`break` outside of a loop".to_string()
+        ))
+    );
+    assert_eq!(
+        parse(lex("inner: while true { continue outer; }")?),
+        Err(DynoError::ControlFlowError(
+            "`continue outer` doesn't refer to an enclosing loop".to_string()
+        ))
+    );
+    assert!(parse(lex("while true { let a: u8; a = { break; 1 }; }")?).is_err());
+    Ok(())
+}
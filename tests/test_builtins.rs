@@ -0,0 +1,109 @@
+mod common;
+use common::assert_parity;
+
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::parser::parse;
+use dyno::types::DynoType;
+
+#[test]
+fn builtin_count_ones() -> DynoResult<()> {
+    assert_parity("return count_ones(11);", 3)?;
+    assert_parity("let a: u64; a = 0; return count_ones(a - 1);", 64)
+}
+
+#[test]
+fn builtin_trailing_zeros() -> DynoResult<()> {
+    assert_parity("return trailing_zeros(8);", 3)?;
+    assert_parity("let a: u8; a = 0; return trailing_zeros(a);", 8)?;
+    assert_parity("let a: u16; a = 0; return trailing_zeros(a);", 16)?;
+    assert_parity("let a: u32; a = 0; return trailing_zeros(a);", 32)?;
+    assert_parity("let a: u64; a = 0; return trailing_zeros(a);", 64)
+}
+
+#[test]
+fn builtin_leading_zeros_width() -> DynoResult<()> {
+    assert_parity("let a: u8; a = 1; return leading_zeros(a);", 7)?;
+    assert_parity("let a: u16; a = 1; return leading_zeros(a);", 15)?;
+    assert_parity("let a: u32; a = 1; return leading_zeros(a);", 31)?;
+    assert_parity("let a: u64; a = 1; return leading_zeros(a);", 63)
+}
+
+#[test]
+fn builtin_wrapped_argument() -> DynoResult<()> {
+    // the subtraction wraps around within the 8 bits of the argument
+    assert_parity("let a: u8; a = 0; return count_ones(a - 1);", 8)?;
+    assert_parity("let a: u8; a = 255; return trailing_zeros(a + 1);", 8)
+}
+
+#[test]
+fn builtin_bool_argument() -> DynoResult<()> {
+    assert_eq!(
+        parse(lex("return count_ones(true);")?),
+        Err(DynoError::IncompatibleTypeError(
+            DynoType::UInt64(),
+            DynoType::Bool(),
+            Some(18..22)
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn method_saturating_add() -> DynoResult<()> {
+    assert_parity("return 200.saturating_add(100);", 255)?;
+    assert_parity("let a: u8; a = 200; return a.saturating_add(55);", 255)?;
+    assert_parity(
+        "let a: u16; a = 65000; return a.saturating_add(1000);",
+        65535,
+    )?;
+    assert_parity(
+        "let a: u32; a = 4294967295; return a.saturating_add(1);",
+        4294967295,
+    )?;
+    assert_parity(
+        "let a: u64; a = 18446744073709551615; return a.saturating_add(1);",
+        u64::MAX,
+    )?;
+    assert_parity("let a: u64; a = 5; return a.saturating_add(6);", 11)
+}
+
+#[test]
+fn method_saturating_sub_mul() -> DynoResult<()> {
+    assert_parity("let a: u8; a = 3; return a.saturating_sub(5);", 0)?;
+    assert_parity("let a: u64; a = 9; return a.saturating_sub(5);", 4)?;
+    assert_parity("let a: u8; a = 16; return a.saturating_mul(16);", 255)?;
+    assert_parity(
+        "let a: u32; a = 65536; return a.saturating_mul(65536);",
+        4294967295,
+    )?;
+    assert_parity(
+        "let a: u64; a = 4294967296; return a.saturating_mul(a);",
+        u64::MAX,
+    )?;
+    assert_parity(
+        "let a: u64; a = 4294967296; return a.saturating_mul(3);",
+        12884901888,
+    )
+}
+
+#[test]
+fn method_wrapping() -> DynoResult<()> {
+    assert_parity("return 200.wrapping_add(100);", 44)?;
+    assert_parity("let a: u8; a = 3; return a.wrapping_sub(5);", 254)?;
+    assert_parity("let a: u16; a = 256; return a.wrapping_mul(256);", 0)?;
+    // the receiver is wrapped before the saturating addition
+    assert_parity("let a: u8; a = 255; return (a + 2).saturating_add(1);", 2)
+}
+
+#[test]
+fn method_unknown_or_on_bool() -> DynoResult<()> {
+    assert_eq!(
+        parse(lex("return 1.checked_add(2);")?),
+        Err(DynoError::IdentifierError(
+            "Unknown method `checked_add`".to_string()
+        ))
+    );
+    assert!(parse(lex("return true.wrapping_add(1);")?).is_err());
+    Ok(())
+}
@@ -0,0 +1,54 @@
+mod common;
+use common::assert_parity;
+
+use dyno::backend::interp;
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::optimizer::fold_constants;
+use dyno::parser::parse;
+
+/// Checks the x86 backend, the interpreter and the constant folder all agree on the result.
+fn assert_folded_parity(input: &str, value: u64) -> DynoResult<()> {
+    assert_eq!(interp::run(&fold_constants(parse(lex(input)?)?))?, value);
+    assert_parity(input, value)
+}
+
+#[test]
+fn cast_literals() -> DynoResult<()> {
+    assert_folded_parity("return 300 as u8;", 44)?;
+    assert_folded_parity("return 300 as! u8;", 255)?;
+    assert_folded_parity("return 200 as! u8;", 200)?;
+    assert_folded_parity("return 70000 as u16;", 4464)
+}
+
+#[test]
+fn cast_variables() -> DynoResult<()> {
+    let program = |cast: &str| {
+        format!(
+            "let a: u64; a = 5000000000; let b: u32; b = {}; return b;",
+            cast
+        )
+    };
+    assert_folded_parity(&program("a as u32"), 705032704)?;
+    assert_folded_parity(&program("a as! u32"), 4294967295)?;
+    assert_folded_parity(&program("a as! u16 as u32"), 65535)
+}
+
+#[test]
+fn cast_precedence() -> DynoResult<()> {
+    assert_folded_parity("let a: u16; a = 300; return a as! u8 - 5;", 250)?;
+    assert_folded_parity("let a: u16; a = 250; return (a + 10) as! u8;", 255)
+}
+
+#[test]
+fn cast_requires_integers() -> DynoResult<()> {
+    assert!(matches!(
+        parse(lex("return true as u8;")?),
+        Err(DynoError::IncompatibleTypeError(_, _, _))
+    ));
+    assert!(matches!(
+        parse(lex("return 1 as bool;")?),
+        Err(DynoError::IncompatibleTypeError(_, _, _))
+    ));
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn cli_dump_ast_to_stderr() {
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = "target/cli/dump.dyno";
+    std::fs::write(path, "let a: u8; a = 4; return a * 2;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--ast", path])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "8\n");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Ast:"));
+    assert!(stderr.contains("Declaration("));
+    assert!(!stderr.contains("Tokens:"));
+}
+
+#[test]
+fn cli_dump_tokens_compact() {
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = "target/cli/tokens.dyno";
+    std::fs::write(path, "return 2 + x;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--lex", path])
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr
+        .contains("Tokens:\nreturn@0..6 int(\"2\")@7..8 +@9..10 ident(\"x\")@11..12 ;@12..13\n"));
+}
+
+#[test]
+fn cli_missing_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .arg("target/cli/missing.dyno")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Failed to read"));
+}
+
+fn run_with_stdin(name: &str, source: &str, stdin: &str) -> String {
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = format!("target/cli/{}.dyno", name);
+    std::fs::write(&path, source).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn cli_read_int() {
+    assert_eq!(
+        run_with_stdin("read_int", "return read_int() * 2;", "7\n"),
+        "14\n"
+    );
+}
+
+#[test]
+fn cli_read_int_keeps_registers() {
+    let source = "let a: u64; a = 10; return a + read_int() * (a - read_int());";
+    assert_eq!(
+        run_with_stdin("read_int_registers", source, "3 4\n"),
+        "28\n"
+    );
+    assert_eq!(run_with_stdin("read_int_empty", source, ""), "10\n");
+}
+
+#[test]
+fn cli_compile_executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = "target/cli/compile.dyno";
+    let executable = "target/cli/compile.out";
+    std::fs::write(path, "let a: u8; a = 20; return a + 1;").unwrap();
+    let _ = std::fs::remove_file(executable);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--compile", path, "-o", executable])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let permissions = std::fs::metadata(executable).unwrap().permissions();
+    assert_ne!(permissions.mode() & 0o111, 0);
+
+    let output = Command::new(executable).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "21\n");
+}
+
+#[test]
+fn cli_compile_without_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--compile", "target/cli/compile.dyno"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Usage:"));
+}
+
+#[test]
+fn cli_repl_blank_line() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"\nreturn 3;\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "> => 0\n> => 3\n> "
+    );
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn cli_keep_artifacts() {
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = "target/cli/keep.dyno";
+    std::fs::write(path, "return 5;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--x86-keep", path])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5\n");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stderr.lines() {
+        let kept = line.rsplit(' ').next().unwrap();
+        assert!(std::path::Path::new(kept).exists());
+    }
+    assert_eq!(stderr.lines().count(), 2);
+}
+
+#[test]
+fn cli_unreachable_warning() {
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = "target/cli/unreachable.dyno";
+    std::fs::write(path, "return 1; return 2;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .arg(path)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Warning: unreachable statement `return 2;`\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--strict", path])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unreachable statement"));
+}
+
+#[test]
+fn cli_emit_cfg() {
+    std::fs::create_dir_all("target/cli").unwrap();
+    let path = "target/cli/cfg.dyno";
+    std::fs::write(path, "let a: u8; if a == 0 { a = 1; } return a;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dyno"))
+        .args(["--emit-cfg", path])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("digraph cfg {"));
+    assert!(stdout.contains("b0 -> b2 [label=\"true\"];"));
+    assert!(stdout.contains("b0 -> b3 [label=\"false\"];"));
+}
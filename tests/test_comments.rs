@@ -0,0 +1,54 @@
+mod common;
+use common::assert_parity;
+
+use dyno::backend::interp;
+use dyno::error::DynoResult;
+use dyno::lexer::Lexer;
+use dyno::parser::parse;
+
+/// Runs the input with comments stripped by the lexer and with comments kept for the parser.
+fn assert_comment_parity(input: &str, value: u64) -> DynoResult<()> {
+    let tokens = Lexer::new(input).keep_comments(true).get_tokens()?;
+    assert_eq!(interp::run(&parse(tokens)?)?, value);
+
+    assert_parity(input, value)
+}
+
+#[test]
+fn comment_inside_expression() -> DynoResult<()> {
+    assert_comment_parity("return 1 + // one\n 2 * 3;", 7)?;
+    assert_comment_parity("return (1 // one\n + 2) * 3;", 9)?;
+    assert_comment_parity("let a: u8; a = 2; return a // a\n == // two\n 2;", 1)
+}
+
+#[test]
+fn comment_between_statements() -> DynoResult<()> {
+    assert_comment_parity(
+        r"
+        // the counter
+        let a: u32;
+        a = 0;
+        // count to three
+        while a < 3 {
+            // inside the body
+            a = a + 1;
+        }
+        // done
+        return a;",
+        3,
+    )
+}
+
+#[test]
+fn comment_at_end_of_input() -> DynoResult<()> {
+    assert_comment_parity("return 4; // done", 4)?;
+    assert_comment_parity("return 4;\n// done\n// really\n", 4)
+}
+
+#[test]
+fn block_comment_inside_expression() -> DynoResult<()> {
+    assert_comment_parity("return 1 + /* x */ 2;", 3)?;
+    assert_comment_parity("return 1 + /* x */ 2 * /* y */ 3;", 7)?;
+    assert_comment_parity("return (1 /* one\n two */ + 2) * 3;", 9)?;
+    assert_comment_parity("/* first */ let a: u8; a = /**/ 2; return a; /* done */", 2)
+}
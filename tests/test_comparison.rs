@@ -0,0 +1,62 @@
+mod common;
+use common::assert_run;
+
+use dyno::backend::interp;
+use dyno::error::DynoResult;
+use dyno::lexer::lex;
+use dyno::parser::parse;
+
+#[test]
+fn execute_comparison_results() -> DynoResult<()> {
+    assert_run("return 5 > 3;", 1)?;
+    assert_run("return 2 > 9;", 0)
+}
+
+#[test]
+fn execute_compare_comparison_results() -> DynoResult<()> {
+    // these only hold if both results are exactly 0 or 1 in the full register
+    assert_run("return (5 > 3) == (9 > 2);", 1)?;
+    assert_run("return (5 > 3) != (2 > 9);", 1)?;
+    assert_run("return (3 <= 3) == (4 >= 5);", 0)
+}
+
+#[test]
+fn execute_bool_equality() -> DynoResult<()> {
+    assert_run(
+        r"
+        let a: bool;
+        a = true;
+        if a == true {
+            return 1;
+        }
+        return 0;",
+        1,
+    )?;
+
+    assert_run(
+        r"
+        let a: bool;
+        a = 1 == 2;
+        if a != false {
+            return 1;
+        }
+        return 0;",
+        0,
+    )
+}
+
+#[test]
+fn execute_return_comparison() -> DynoResult<()> {
+    let expected = [
+        ("return 1 == 1;", 1),
+        ("return 1 != 1;", 0),
+        ("return (1 == 1);", 1),
+        ("return (300 > 2);", 1),
+    ];
+
+    for (input, value) in expected.iter() {
+        assert_run(input, *value)?;
+        assert_eq!(interp::run(&parse(lex(input)?)?)?, *value);
+    }
+    Ok(())
+}
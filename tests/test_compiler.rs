@@ -0,0 +1,14 @@
+use dyno::backend::x86_backend::compile_and_run;
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::parser::parse;
+
+#[test]
+fn execute_with_bogus_compiler() -> DynoResult<()> {
+    // this is the only test in this file so changing the environment can't affect other tests
+    std::env::set_var("DYNO_CC", "dyno_bogus_cc");
+
+    let result = compile_and_run(&parse(lex("return 42;")?)?);
+    assert!(matches!(result, Err(DynoError::GeneratorError(_))));
+    Ok(())
+}
@@ -0,0 +1,45 @@
+mod common;
+use common::assert_parity;
+
+use dyno::error::DynoResult;
+
+#[test]
+fn conditional_simple() -> DynoResult<()> {
+    assert_parity("return 1 == 1 ? 5 : 9;", 5)?;
+    assert_parity("return 1 == 2 ? 5 : 9;", 9)
+}
+
+#[test]
+fn conditional_nested() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u8;
+        a = 7;
+        return a < 5 ? 1 : a < 10 ? 2 : 3;",
+        2,
+    )
+}
+
+#[test]
+fn conditional_widen_arms() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u16;
+        a = 300;
+        let b: u8;
+        b = a > 200 ? 2 : 1;
+        return a > 200 ? a - 290 : b;",
+        10,
+    )
+}
+
+#[test]
+fn conditional_arms_use_registers() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u8;
+        a = 4;
+        return 1 + (a == 4 ? 2 : (a + 1) * (a + 2));",
+        3,
+    )
+}
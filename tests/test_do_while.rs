@@ -0,0 +1,52 @@
+mod common;
+use common::assert_parity;
+
+use dyno::error::DynoResult;
+
+#[test]
+fn do_while_runs_once() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u32;
+        a = 0;
+        do {
+            a = a + 7;
+        } while false;
+        return a;",
+        7,
+    )
+}
+
+#[test]
+fn do_while_counts() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u32;
+        a = 0;
+        do {
+            a = a + 1;
+        } while a < 10;
+        return a;",
+        10,
+    )
+}
+
+#[test]
+fn do_while_continue_checks_condition() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let a: u32;
+        let b: u32;
+        a = 0;
+        b = 0;
+        outer: do {
+            a = a + 1;
+            if a > 3 {
+                continue outer;
+            }
+            b = b + 1;
+        } while a < 6;
+        return a * 10 + b;",
+        63,
+    )
+}
@@ -0,0 +1,33 @@
+use dyno::error::DynoResult;
+use dyno::eval;
+use dyno::types::DynoType;
+
+#[test]
+fn eval_value_and_type() -> DynoResult<()> {
+    assert_eq!(eval("return 300;")?, (300, DynoType::UInt16()));
+    assert_eq!(eval("return 1 == 1;")?, (1, DynoType::Bool()));
+    assert_eq!(
+        eval("let a: u64; a = 3; if a > 2 { return a; } return 1;")?,
+        (3, DynoType::UInt64())
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_widest_return_type() -> DynoResult<()> {
+    assert_eq!(
+        eval("let c: bool; c = false; if c { return 1; } return 300;")?,
+        (300, DynoType::UInt16())
+    );
+    assert_eq!(
+        eval("let a: u32; a = 7; if a > 9 { return 1; } return a;")?,
+        (7, DynoType::UInt32())
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_without_return() -> DynoResult<()> {
+    assert_eq!(eval("let a: u8; a = 3;")?, (0, DynoType::Void()));
+    Ok(())
+}
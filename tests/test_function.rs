@@ -0,0 +1,127 @@
+use dyno::backend::x86_backend::generate_function;
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::parser::parse_function_body;
+use dyno::types::DynoType;
+use std::fs::File;
+use std::process::Command;
+
+/// The name, parameters and body of a dyno function.
+type Function<'a> = (&'a str, &'a [(String, DynoType)], &'a str);
+
+/// Compiles dyno functions into a single assembly file together with a C file containing `main`,
+/// and returns what the program prints.
+fn run_with_c(functions: &[Function], c_main: &str) -> DynoResult<String> {
+    let directory = format!("target/x86/function_{}", functions[0].0);
+    std::fs::create_dir_all(&directory)?;
+    let assembly_file = format!("{}/functions.s", directory);
+    let c_file = format!("{}/main.c", directory);
+    let executable = format!("{}/main.out", directory);
+
+    let mut writer = File::create(&assembly_file)?;
+    for (name, params, body) in functions {
+        let (ast, _) = parse_function_body(lex(body)?, params)?;
+        generate_function(name, params, &ast, &mut writer)?;
+    }
+    std::fs::write(&c_file, c_main)?;
+
+    let compiler = std::env::var("DYNO_CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .args([&c_file, &assembly_file, "-o", &executable])
+        .status()?;
+    assert!(status.success());
+
+    let output = Command::new(&executable).output()?;
+    let _ = std::fs::remove_dir_all(&directory);
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[test]
+fn function_called_from_c() -> DynoResult<()> {
+    let params = [
+        ("a".to_string(), DynoType::UInt64()),
+        ("b".to_string(), DynoType::UInt64()),
+    ];
+    let c_main = r#"
+#include <stdint.h>
+#include <stdio.h>
+
+uint64_t add(uint64_t a, uint64_t b);
+
+int main() {
+    printf("%lu %lu\n", add(2, 3), add(40, 2));
+    return 0;
+}
+"#;
+    assert_eq!(
+        run_with_c(&[("add", &params, "return a + b;")], c_main)?,
+        "5 42\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn function_narrow_parameters() -> DynoResult<()> {
+    // only the bits of the parameter types are read, so the arguments wrap around
+    let params = [
+        ("a".to_string(), DynoType::UInt8()),
+        ("b".to_string(), DynoType::UInt16()),
+        ("c".to_string(), DynoType::UInt32()),
+        ("d".to_string(), DynoType::UInt64()),
+        ("e".to_string(), DynoType::UInt64()),
+        ("f".to_string(), DynoType::UInt64()),
+    ];
+    let c_main = r#"
+#include <stdint.h>
+#include <stdio.h>
+
+uint64_t sum(uint64_t a, uint64_t b, uint64_t c, uint64_t d, uint64_t e, uint64_t f);
+
+int main() {
+    printf("%lu\n", sum(257, 65537, 1, 1000, 20000, 300000));
+    return 0;
+}
+"#;
+    let body = "let x: u64; x = a; x = x + b + c + d; return x + e + f;";
+    assert_eq!(run_with_c(&[("sum", &params, body)], c_main)?, "321003\n");
+    Ok(())
+}
+
+#[test]
+fn functions_in_one_file() -> DynoResult<()> {
+    let params = [("n".to_string(), DynoType::UInt64())];
+    let c_main = r#"
+#include <stdint.h>
+#include <stdio.h>
+
+uint64_t triangle(uint64_t n);
+uint64_t clamp(uint64_t n);
+
+int main() {
+    printf("%lu %lu %lu\n", triangle(4), clamp(3), clamp(30));
+    return 0;
+}
+"#;
+    let triangle = "let t: u64; t = 0; while n > 0 { t = t + n; n = n - 1; } return t;";
+    let clamp = "while n > 10 { return 10; } if n < 5 { return 5; } return n;";
+    assert_eq!(
+        run_with_c(
+            &[("triangle", &params, triangle), ("clamp", &params, clamp)],
+            c_main
+        )?,
+        "10 5 10\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn function_too_many_parameters() -> DynoResult<()> {
+    let params: Vec<(String, DynoType)> = (0..7)
+        .map(|i| (format!("p{}", i), DynoType::UInt64()))
+        .collect();
+    let (ast, _) = parse_function_body(lex("return 1;")?, &params)?;
+
+    let result = generate_function("f", &params, &ast, &mut Vec::new());
+    assert!(matches!(result, Err(DynoError::GeneratorError(_))));
+    Ok(())
+}
@@ -1,7 +1,11 @@
 mod common;
 use common::assert_run;
 
+use dyno::backend::x86_backend::compile_and_run;
 use dyno::error::DynoResult;
+use dyno::lexer::lex;
+use dyno::optimizer::optimize;
+use dyno::parser::parse;
 
 #[test]
 fn execute_simple_if() -> DynoResult<()> {
@@ -12,3 +16,30 @@ fn execute_simple_if() -> DynoResult<()> {
 fn execute_simple_if_false() -> DynoResult<()> {
     assert_run("let a: u32; a = 24; if 1 == 0 { a = 42; } return a;", 24)
 }
+
+#[test]
+fn execute_if_else() -> DynoResult<()> {
+    let program = r"
+        let a: u32;
+        a = INPUT;
+        let b: u32;
+        if a > 2 {
+            b = a * 2 + 1;
+        } else if a == 1 {
+            b = (a + 4) * (a + 5);
+        } else {
+            b = 100 - a;
+        }
+        return b;";
+
+    assert_run(&program.replace("INPUT", "3"), 7)?;
+    assert_run(&program.replace("INPUT", "1"), 30)?;
+    assert_run(&program.replace("INPUT", "2"), 98)
+}
+
+#[test]
+fn execute_optimized_constant_if() -> DynoResult<()> {
+    let ast = optimize(parse(lex("if 1 == 0 { return 5; } return 7;")?)?);
+    assert_eq!(compile_and_run(&ast)?, 7);
+    Ok(())
+}
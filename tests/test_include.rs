@@ -0,0 +1,56 @@
+mod common;
+use common::assert_run;
+
+use dyno::error::{DynoError, DynoResult};
+use dyno::expand_includes;
+
+fn write_files(directory: &str, files: &[(&str, &str)]) -> DynoResult<()> {
+    std::fs::create_dir_all(directory)?;
+    for (name, contents) in files {
+        std::fs::write(format!("{}/{}", directory, name), contents)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn include_file() -> DynoResult<()> {
+    let directory = "target/include/simple";
+    write_files(
+        &format!("{}/lib", directory),
+        &[("counter.dyno", "let counter: u8;\ncounter = 41;\n")],
+    )?;
+    write_files(
+        directory,
+        &[(
+            "main.dyno",
+            "include \"lib/counter.dyno\";\nreturn counter + 1;\n",
+        )],
+    )?;
+
+    let input = expand_includes(format!("{}/main.dyno", directory))?;
+    assert_eq!(
+        input,
+        "let counter: u8;\ncounter = 41;\nreturn counter + 1;\n"
+    );
+    assert_run(&input, 42)
+}
+
+#[test]
+fn include_cycle() -> DynoResult<()> {
+    let directory = "target/include/cycle";
+    write_files(
+        directory,
+        &[
+            ("a.dyno", "include \"b.dyno\";\nreturn 1;\n"),
+            ("b.dyno", "include \"a.dyno\";\n"),
+        ],
+    )?;
+
+    match expand_includes(format!("{}/a.dyno", directory)) {
+        Err(DynoError::IncludeError(message)) => {
+            assert!(message.ends_with("a.dyno includes itself"))
+        }
+        x => panic!("expected an include error, got {:?}", x),
+    }
+    Ok(())
+}
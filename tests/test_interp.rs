@@ -0,0 +1,74 @@
+mod common;
+use common::assert_parity;
+
+use dyno::error::DynoResult;
+
+#[test]
+fn interp_arithmetic() -> DynoResult<()> {
+    assert_parity("return 42 - 12 + 12 - 5 + 2284 - 2300;", 21)?;
+    assert_parity("return 12 / 3 + 7 * 8 - 10 / 2 * 4;", 40)
+}
+
+#[test]
+fn interp_variables() -> DynoResult<()> {
+    assert_parity(
+        r"
+        let x: u32;
+        x = 13;
+        let y: u16;
+        y = 12;
+        return x * y;",
+        156,
+    )?;
+
+    assert_parity(
+        r"
+        let x: u32;
+        x = 13;
+        {
+            let x: u16;
+            x = 12;
+        }
+        return x;",
+        13,
+    )
+}
+
+#[test]
+fn interp_if() -> DynoResult<()> {
+    assert_parity("let a: u32; a = 24; if 1 == 1 { a = 42; } return a;", 42)?;
+    assert_parity("let a: u32; a = 24; if 1 == 0 { a = 42; } return a;", 24)
+}
+
+#[test]
+fn interp_while() -> DynoResult<()> {
+    assert_parity(
+        "let a: u32; a = 10; let b: u16; b = 5; while a > b { a = a - 1; } return a;",
+        5,
+    )
+}
+
+#[test]
+fn interp_no_return() -> DynoResult<()> {
+    assert_parity("let x: u32; x = 5;", 0)?;
+    assert_parity("let x: u32; x = 5; if x == 4 { return 1; }", 0)
+}
+
+#[test]
+fn interp_empty_program() -> DynoResult<()> {
+    assert_parity("", 0)?;
+    assert_parity("  \n", 0)
+}
+
+#[test]
+fn interp_unstored_results_wrap() -> DynoResult<()> {
+    // arithmetic wraps at every operation, not only when the result is stored
+    assert_parity("return 255 + 1;", 0)?;
+    assert_parity("let a: u8; a = 0; return a - 1;", 255)?;
+    assert_parity(
+        "let a: u8; a = 200; if (a + 100) < 50 { return 1; } return 0;",
+        1,
+    )?;
+    assert_parity("let a: u16; a = 65535; return (a + 1) / 2;", 0)?;
+    assert_parity("let a: u32; a = 65536; return a * a;", 0)
+}
@@ -1,7 +1,11 @@
 mod common;
 use common::assert_run;
 
-use dyno::error::DynoResult;
+use dyno::backend::x86_backend::{compile_and_run, compile_and_run_with_config, X86Config};
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::optimizer::{fold_constants, optimize};
+use dyno::parser::parse;
 
 #[test]
 fn limits_u8() -> DynoResult<()> {
@@ -79,3 +83,60 @@ fn limits_u64() -> DynoResult<()> {
         18446744073709551615,
     )
 }
+
+#[test]
+fn checked_underflow_u8() -> DynoResult<()> {
+    let config = X86Config {
+        checked: true,
+        ..X86Config::default()
+    };
+
+    let ast = parse(lex(r"
+        let x: u8;
+        x = 0 - 1;
+        return x;")?)?;
+    assert_eq!(
+        compile_and_run_with_config(&ast, &config),
+        Err(DynoError::VisitError(
+            "subtraction `0 - 1` underflows".to_string()
+        ))
+    );
+
+    // the sum wraps around to 44 before the subtraction, so this traps at runtime
+    let ast = parse(lex(r"
+        let a: u8;
+        let x: u8;
+        a = 200;
+        x = (a + 100) - 50;
+        return x;")?)?;
+    assert!(matches!(
+        compile_and_run_with_config(&ast, &config),
+        Err(DynoError::GeneratorError(_))
+    ));
+
+    let ast = parse(lex(r"
+        let x: u8;
+        x = 3 - 1;
+        return x;")?)?;
+    assert_eq!(compile_and_run_with_config(&ast, &config)?, 2);
+    Ok(())
+}
+
+#[test]
+fn limits_folded() -> DynoResult<()> {
+    for input in &[
+        "let x: u8; x = 255 + 1; return x;",
+        "let x: u8; x = 0 - 1; return x;",
+        "let x: u8; x = 16 * 16 + 7; return x;",
+        // results which are returned without being stored wrap around as well
+        "return 255 + 1;",
+        "return 0 - 1;",
+        "if 1 == 1 { return 255 + 1; } return 0;",
+    ] {
+        let ast = parse(lex(input)?)?;
+        let expected = compile_and_run(&ast)?;
+        assert_eq!(compile_and_run(&fold_constants(ast))?, expected);
+        assert_eq!(compile_and_run(&optimize(parse(lex(input)?)?))?, expected);
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use dyno::backend::x86_backend::compile_and_run;
+use dyno::error::{DynoError, DynoResult};
+use dyno::lexer::lex;
+use dyno::parser::parse;
+
+#[test]
+fn execute_divide_by_zero() -> DynoResult<()> {
+    let ast = parse(lex(r"
+        let a: u8;
+        a = 0;
+        return 5 / a;")?)?;
+
+    match compile_and_run(&ast) {
+        Err(DynoError::GeneratorError(message)) => assert_eq!(message, "runtime fault: SIGFPE"),
+        x => panic!("expected a runtime fault, got {:?}", x),
+    }
+    Ok(())
+}
+
+#[test]
+fn execute_result_distinct_from_signal() -> DynoResult<()> {
+    // 139 is the exit code a shell reports for a segmentation fault
+    assert_eq!(compile_and_run(&parse(lex("return 139;")?)?)?, 139);
+    assert_eq!(compile_and_run(&parse(lex("return 256;")?)?)?, 256);
+
+    let ast = parse(lex("assert(1 == 2); return 139;")?)?;
+    match compile_and_run(&ast) {
+        Err(DynoError::GeneratorError(message)) => assert_eq!(message, "runtime fault: SIGILL"),
+        x => panic!("expected a runtime fault, got {:?}", x),
+    }
+    Ok(())
+}
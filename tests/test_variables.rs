@@ -41,3 +41,48 @@ fn execute_same_variable_different_scope() -> DynoResult<()> {
         13,
     )
 }
+
+#[test]
+fn execute_store_comparison_in_bool() -> DynoResult<()> {
+    assert_run(
+        r"
+        let b: bool;
+        b = 1 == 1;
+        let x: u32;
+        x = 13;
+        if b {
+            x = 42;
+        }
+        return x;",
+        42,
+    )?;
+
+    assert_run(
+        r"
+        let b: bool;
+        b = 1 == 2;
+        let x: u32;
+        x = 13;
+        if b {
+            x = 42;
+        }
+        return x;",
+        13,
+    )
+}
+
+#[test]
+fn execute_named_constants() -> DynoResult<()> {
+    assert_run(
+        r"
+        const STEP: u32 = 3;
+        const LIMIT: u32 = STEP * 10;
+        let x: u32;
+        x = 0;
+        while x < LIMIT {
+            x = x + STEP;
+        }
+        return x;",
+        30,
+    )
+}
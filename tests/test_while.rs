@@ -18,3 +18,65 @@ fn execute_while() -> DynoResult<()> {
         5,
     )
 }
+
+#[test]
+fn execute_if_inside_while() -> DynoResult<()> {
+    assert_run(
+        r"
+        let a: u32;
+        a = 10;
+        let b: u32;
+        b = 0;
+        while a > 0 {
+            if a == 5 {
+                b = b + 7;
+            }
+            if a == 2 {
+                b = b + 3;
+            }
+            a = a - 1;
+        }
+        return b;",
+        10,
+    )
+}
+
+#[test]
+fn execute_while_bool_variable() -> DynoResult<()> {
+    assert_run(
+        r"
+        let a: bool;
+        a = true;
+        while a {
+            a = false;
+        }
+        return 0;",
+        0,
+    )?;
+
+    assert_run(
+        r"
+        let a: bool;
+        let b: u8;
+        a = true;
+        b = 0;
+        while a {
+            b = b + 1;
+            a = b < 5;
+        }
+        if a == false {
+            return b;
+        }
+        return 0;",
+        5,
+    )
+}
+
+#[test]
+fn execute_declaration_in_loop_body() -> DynoResult<()> {
+    // the slot of `t` is reserved once, declaring it on every iteration doesn't grow the stack
+    assert_run(
+        "let i: u64; i = 0; while i < 10000000 { let t: u64; t = i; i = i + 1; } return i;",
+        10000000,
+    )
+}